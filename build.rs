@@ -1,4 +1,4 @@
-use leptos_i18n_build::{Config, TranslationsInfos};
+use leptos_i18n_build::{Config, ParseOptions, TranslationsInfos};
 use std::error::Error;
 use std::path::PathBuf;
 
@@ -12,7 +12,11 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // where to generate the translations
     let i18n_mod_directory = PathBuf::from(std::env::var_os("OUT_DIR").unwrap()).join("i18n");
-    let cfg = Config::new("de")?.add_locale("en")?;
+    // `t_string!` with interpolated values needs `interpolate_display`
+    let options = ParseOptions::new().interpolate_display(true);
+    let cfg = Config::new("de")?
+        .add_locale("en")?
+        .parse_options(options);
 
     let translations_infos = TranslationsInfos::parse(cfg)?;
 
@@ -0,0 +1,137 @@
+// A minimal OpenID Connect provider for the e2e tests of the single sign-on.
+//
+// The test configuration has to contain:
+//   "oidc": {
+//     "discovery_url": "http://localhost:3457/.well-known/openid-configuration",
+//     "client_id": "lsf-test",
+//     "client_secret": "lsf-test-secret",
+//     "redirect_url": "http://localhost:3456/oidc-callback",
+//     "link_claim": "preferred_username",
+//     "provision_accounts": true,
+//     "login_expiry_mins": 5
+//   }
+//
+// The login form asks for a username only, which becomes the subject and the
+// preferred_username claim.
+import {createHash, createSign, generateKeyPairSync, randomBytes} from "node:crypto";
+import {createServer} from "node:http";
+
+const PORT = 3457;
+const ISSUER = `http://localhost:${PORT}`;
+const CLIENT_ID = "lsf-test";
+const CLIENT_SECRET = "lsf-test-secret";
+const KID = "mock-key";
+
+const {privateKey, publicKey} = generateKeyPairSync("rsa", {modulusLength: 2048});
+const jwk = {...publicKey.export({format: "jwk"}), kid: KID, alg: "RS256", use: "sig"};
+// code -> the authorization request it was issued for
+const codes = new Map();
+
+const base64url = (input) => Buffer.from(input).toString("base64url");
+
+function idToken(claims) {
+    const header = base64url(JSON.stringify({alg: "RS256", typ: "JWT", kid: KID}));
+    const payload = base64url(JSON.stringify(claims));
+    const signature = createSign("RSA-SHA256").update(`${header}.${payload}`).sign(privateKey);
+    return `${header}.${payload}.${base64url(signature)}`;
+}
+
+function json(res, status, body) {
+    res.writeHead(status, {"Content-Type": "application/json"});
+    res.end(JSON.stringify(body));
+}
+
+function escapeHtml(value) {
+    return String(value).replace(/[&<>"']/g, (c) => `&#${c.charCodeAt(0)};`);
+}
+
+async function readBody(req) {
+    let body = "";
+    for await (const chunk of req) body += chunk;
+    return new URLSearchParams(body);
+}
+
+createServer(async (req, res) => {
+    const url = new URL(req.url, ISSUER);
+
+    if (url.pathname === "/.well-known/openid-configuration") {
+        return json(res, 200, {
+            issuer: ISSUER,
+            authorization_endpoint: `${ISSUER}/authorize`,
+            token_endpoint: `${ISSUER}/token`,
+            jwks_uri: `${ISSUER}/jwks`,
+            response_types_supported: ["code"],
+            subject_types_supported: ["public"],
+            id_token_signing_alg_values_supported: ["RS256"],
+            code_challenge_methods_supported: ["S256"],
+        });
+    }
+
+    if (url.pathname === "/jwks") {
+        return json(res, 200, {keys: [jwk]});
+    }
+
+    if (url.pathname === "/authorize" && req.method === "GET") {
+        const params = url.searchParams;
+        if (params.get("client_id") !== CLIENT_ID || params.get("code_challenge_method") !== "S256") {
+            return json(res, 400, {error: "invalid_request"});
+        }
+        const hidden = ["redirect_uri", "state", "nonce", "code_challenge"]
+            .map((name) => `<input type="hidden" name="${name}" value="${escapeHtml(params.get(name) ?? "")}">`)
+            .join("");
+        res.writeHead(200, {"Content-Type": "text/html"});
+        return res.end(`<!DOCTYPE html><html><body><h1>Mock provider</h1>
+<form method="post" action="/authorize">${hidden}
+<label for="username">Provider username</label><input id="username" name="username">
+<button type="submit">Sign in at provider</button></form></body></html>`);
+    }
+
+    if (url.pathname === "/authorize" && req.method === "POST") {
+        const form = await readBody(req);
+        const code = randomBytes(16).toString("hex");
+        codes.set(code, {
+            username: form.get("username"),
+            redirectUri: form.get("redirect_uri"),
+            nonce: form.get("nonce"),
+            codeChallenge: form.get("code_challenge"),
+        });
+        const redirect = new URL(form.get("redirect_uri"));
+        redirect.searchParams.set("code", code);
+        redirect.searchParams.set("state", form.get("state"));
+        res.writeHead(302, {Location: redirect.toString()});
+        return res.end();
+    }
+
+    if (url.pathname === "/token" && req.method === "POST") {
+        const credentials = Buffer.from((req.headers.authorization ?? "").replace(/^Basic /, ""), "base64").toString();
+        if (credentials !== `${CLIENT_ID}:${CLIENT_SECRET}`) {
+            return json(res, 401, {error: "invalid_client"});
+        }
+        const form = await readBody(req);
+        const request = codes.get(form.get("code"));
+        codes.delete(form.get("code"));
+        const challenge = createHash("sha256").update(form.get("code_verifier") ?? "").digest("base64url");
+        if (!request || request.redirectUri !== form.get("redirect_uri") || request.codeChallenge !== challenge) {
+            return json(res, 400, {error: "invalid_grant"});
+        }
+        const now = Math.floor(Date.now() / 1000);
+        return json(res, 200, {
+            access_token: randomBytes(16).toString("hex"),
+            token_type: "Bearer",
+            expires_in: 300,
+            id_token: idToken({
+                iss: ISSUER,
+                aud: CLIENT_ID,
+                sub: `mock|${request.username}`,
+                preferred_username: request.username,
+                name: `SSO ${request.username}`,
+                email: `${request.username}@example.com`,
+                nonce: request.nonce,
+                iat: now,
+                exp: now + 300,
+            }),
+        });
+    }
+
+    json(res, 404, {error: "not_found"});
+}).listen(PORT, () => console.log(`OIDC mock provider listening on ${ISSUER}`));
@@ -0,0 +1,39 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+const test = mergeTests(dbTest, lpTest);
+
+test('user management is only for admins', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/admin/users");
+    await expect(page.getByRole('heading', {name: i18nHelper.get("en", "forbidden")})).toBeVisible();
+
+    await dbHelper.deleteTestUser(username);
+});
+
+test('a disabled account can not log in', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const admin = await dbHelper.addTestUser('en');
+    await dbHelper.setUserRole(admin, 'admin');
+    const username = await dbHelper.addTestUser('de');
+
+    await loginPage.navigate();
+    await loginPage.login(admin);
+    await expect(page).toHaveURL("/");
+    await page.goto("/admin/users");
+    await page.getByRole('searchbox').fill(username);
+    const row = page.getByRole('row').filter({hasText: username});
+    await row.getByRole('button', {name: i18nHelper.get("en", "disableAccount")}).click();
+    await expect(row.getByText(i18nHelper.get("en", "accountStatusDisabled"))).toBeVisible();
+
+    await loginPage.logout();
+    await loginPage.login(username);
+    await expect(page.getByText(i18nHelper.get("en", "accountDisabled"))).toBeVisible();
+
+    await dbHelper.deleteTestUser(username);
+    await dbHelper.deleteTestUser(admin);
+});
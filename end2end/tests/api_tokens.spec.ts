@@ -0,0 +1,37 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+const test = mergeTests(dbTest, lpTest);
+
+test('a personal API token authorises server fns until it is revoked', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    // the server fn paths are generated, so take the one the profile page calls
+    const getUserRequest = page.waitForRequest(/\/api\/get_user/);
+    await page.goto("/profile");
+    const getUserUrl = (await getUserRequest).url();
+
+    await page.getByRole('textbox', {name: i18nHelper.get("en", "apiTokenName")}).fill("deploy script");
+    await page.getByRole('button', {name: i18nHelper.get("en", "createApiToken")}).click();
+    const token = await page.locator("code").textContent();
+    expect(token).toMatch(/^lsf_[0-9a-f]{64}$/);
+    await expect(page.getByRole('cell', {name: "deploy script"})).toBeVisible();
+
+    const call = async () => (await page.request.post(getUserUrl, {
+        headers: {Authorization: `Bearer ${token}`},
+    })).json();
+    const authorised = await call();
+    expect(authorised.error).toBeNull();
+    expect(authorised.data.name).toBe("Test User");
+
+    await page.getByRole('button', {name: i18nHelper.get("en", "revokeApiToken")}).click();
+    await expect(page.getByRole('cell', {name: "deploy script"})).toHaveCount(0);
+    const revoked = await call();
+    expect(revoked.error).not.toBeNull();
+
+    await dbHelper.deleteTestUser(username);
+});
@@ -0,0 +1,41 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+// The test configuration has to chain the database and the fake auth backend:
+//   "auth_backends": [
+//     {"type": "database"},
+//     {"type": "fake", "users": [{"username": "fake_testuser", "password": "fake-password", "name": "Fake User"}]}
+//   ]
+const test = mergeTests(dbTest, lpTest);
+
+const FAKE_USERNAME = "fake_testuser";
+
+test('the chained backends accept both passwords and sync the name', async ({page, dbHelper, loginPage}) => {
+    // left over, if a previous run failed
+    await dbHelper.deleteTestUser(FAKE_USERNAME);
+    await dbHelper.addUser(FAKE_USERNAME, 'en');
+
+    await loginPage.navigate();
+    await loginPage.login(FAKE_USERNAME);
+    await expect(page).toHaveURL("/");
+    await expect(page.getByRole("navigation")).toContainText("Test User");
+    await loginPage.logout();
+
+    await loginPage.navigate();
+    await loginPage.login(FAKE_USERNAME, "fake-password");
+    await expect(page).toHaveURL("/");
+    await expect(page.getByRole("navigation")).toContainText("Fake User");
+
+    await dbHelper.deleteTestUser(FAKE_USERNAME);
+});
+
+test('a password known to no backend is rejected', async ({dbHelper, loginPage}) => {
+    const username = await dbHelper.addTestUser('en');
+
+    await loginPage.navigate();
+    await loginPage.login(username, "fake-password");
+    await loginPage.expectInvalidCredentialsError();
+
+    await dbHelper.deleteTestUser(username);
+});
@@ -0,0 +1,92 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+// The test configuration has to lock an account after at most `MAX_FAILED_ATTEMPTS`:
+//   "login_lockout": {"max_failed_attempts": 5, ...}
+const test = mergeTests(dbTest, lpTest);
+const MAX_FAILED_ATTEMPTS = 5;
+
+test('a wrong current password is rejected', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/change-password");
+    await page.getByLabel(i18nHelper.get("en", "currentPassword"), {exact: true}).fill("wrong password");
+    await page.getByLabel(i18nHelper.get("en", "newPassword"), {exact: true}).fill("new password");
+    await page.getByLabel(i18nHelper.get("en", "newPasswordRepeated"), {exact: true}).fill("new password");
+    await page.getByRole('button', {name: i18nHelper.get("en", "changePassword")}).click();
+    await expect(page.getByText(i18nHelper.get("en", "wrongCurrentPassword"))).toBeVisible();
+
+    // the password is unchanged
+    await loginPage.logout();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+
+    await dbHelper.deleteTestUser(username);
+});
+
+test('the password is changed and other sessions are ended', async ({page, browser, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+
+    const otherPage = await loginPage.loginInNewContext(browser, username);
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/change-password");
+    await page.getByLabel(i18nHelper.get("en", "currentPassword"), {exact: true}).fill("password");
+    await page.getByLabel(i18nHelper.get("en", "newPassword"), {exact: true}).fill("new password");
+    await page.getByLabel(i18nHelper.get("en", "newPasswordRepeated"), {exact: true}).fill("new password");
+    await page.getByRole('checkbox', {name: i18nHelper.get("en", "revokeOtherSessions")}).check();
+    await page.getByRole('button', {name: i18nHelper.get("en", "changePassword")}).click();
+    await expect(page.getByText(i18nHelper.get("en", "passwordChanged"))).toBeVisible();
+
+    // the other session has ended, the current one is kept
+    await otherPage.reload();
+    await expect(otherPage).toHaveURL(/\/login/);
+    await page.goto("/sessions");
+    await expect(page.getByText(i18nHelper.get("en", "currentSession"))).toBeVisible();
+
+    // only the new password is valid now
+    await loginPage.logout();
+    await loginPage.login(username);
+    await loginPage.expectInvalidCredentialsError();
+    await loginPage.login(username, "new password");
+    await expect(page).toHaveURL("/");
+
+    await otherPage.context().close();
+    await dbHelper.deleteTestUser(username);
+});
+
+test('guessing the current password locks the account', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+    const lockedText = i18nHelper.get("en", "accountLocked").split("{{")[0].trim();
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/change-password");
+    await page.getByLabel(i18nHelper.get("en", "newPassword"), {exact: true}).fill("new password");
+    await page.getByLabel(i18nHelper.get("en", "newPasswordRepeated"), {exact: true}).fill("new password");
+    for (let attempt = 0; attempt < MAX_FAILED_ATTEMPTS; attempt++) {
+        await page.getByLabel(i18nHelper.get("en", "currentPassword"), {exact: true}).fill("wrong password");
+        const response = page.waitForResponse((response) => response.url().includes("/api/change_password"));
+        await page.getByRole('button', {name: i18nHelper.get("en", "changePassword")}).click();
+        await response;
+    }
+
+    // even the right password is refused now
+    await page.getByLabel(i18nHelper.get("en", "currentPassword"), {exact: true}).fill("password");
+    await page.getByRole('button', {name: i18nHelper.get("en", "changePassword")}).click();
+    await expect(page.getByText(lockedText)).toBeVisible();
+
+    // the lock applies to logins, too
+    await loginPage.logout();
+    await loginPage.login(username);
+    await expect(page.getByText(lockedText)).toBeVisible();
+
+    await dbHelper.deleteTestUser(username);
+});
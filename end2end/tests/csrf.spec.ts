@@ -0,0 +1,57 @@
+import {expect, Page, Request} from '@playwright/test';
+import {test} from "./fixtures/loginPage";
+
+// The test configuration has to allow the origin of the tests:
+//   "csrf": {"allowed_origin": "http://localhost:3456"}
+const VALID_USERNAME = "admin";
+const CSRF_HEADER = "x-csrf-token";
+
+// logs in and records a server fn call of the session, which is replayed by the tests
+test.beforeEach(async ({page, loginPage}) => {
+    await loginPage.navigate();
+    await loginPage.login(VALID_USERNAME);
+    await expect(page).toHaveURL("/");
+});
+
+async function recordServerFnCall(page: Page): Promise<Request> {
+    const serverFnCall = page.waitForRequest((request: Request) =>
+        request.url().includes("/api/") && request.headers()[CSRF_HEADER] !== undefined);
+    // restoring the user after the reload calls a server fn of the session
+    await page.reload();
+    return serverFnCall;
+}
+
+test('server fns of a session get the CSRF token', async ({page}) => {
+    const serverFnCall = await recordServerFnCall(page);
+    const response = await page.request.post(serverFnCall.url(), {
+        headers: await serverFnCall.allHeaders(),
+        data: serverFnCall.postData() ?? "",
+    });
+    expect(await response.text()).not.toContain("Forbidden");
+});
+
+test('server fns without the CSRF token are forbidden', async ({page}) => {
+    const serverFnCall = await recordServerFnCall(page);
+    const headers = await serverFnCall.allHeaders();
+    delete headers[CSRF_HEADER];
+    const response = await page.request.post(serverFnCall.url(), {
+        headers,
+        data: serverFnCall.postData() ?? "",
+    });
+    expect(await response.text()).toContain("Forbidden");
+});
+
+test('server fns called by another origin are forbidden', async ({page}) => {
+    const serverFnCall = await recordServerFnCall(page);
+    const response = await page.request.post(serverFnCall.url(), {
+        headers: {...await serverFnCall.allHeaders(), origin: "http://evil.example.com"},
+        data: serverFnCall.postData() ?? "",
+    });
+    expect(await response.text()).toContain("Forbidden");
+});
+
+test('logout clears the CSRF cookie', async ({page, loginPage}) => {
+    await loginPage.logout();
+    const cookies = await page.context().cookies();
+    expect(cookies.find((cookie) => cookie.name === "lsf_csrf")).toBeUndefined();
+});
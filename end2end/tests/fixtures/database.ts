@@ -58,6 +58,12 @@ class DatabaseHelper {
 
     async addTestUser(lang: string) {
         const username = lang + "_testuser_" + this.workerId;
+        await this.addUser(username, lang);
+        return username;
+    }
+
+    // for tests needing a fixed username, e.g., one known to the fake auth backend
+    async addUser(username: string, lang: string) {
         // 'password' hashed by bcrypt with 12 rounds
         const hash = "$2a$12$2W3AcX2RnI3ZJSwrvWbar.x6FL.nK63niONl.d.mv39bTG5Ru/E9G";
         const name = "Test User";
@@ -65,7 +71,16 @@ class DatabaseHelper {
             "INTO account (username, pw_hash, name, preferred_language)\n\t" +
             "VALUES ($1, $2, $3, $4)";
         await this.query(query, [username, hash, name, lang]);
-        return username;
+    }
+
+    async setUserEmail(username: string, email: string) {
+        const query = "UPDATE account SET email = $1 WHERE username = $2";
+        await this.query(query, [email, username]);
+    }
+
+    async setUserRole(username: string, role: string) {
+        const query = "UPDATE account SET role = $1 WHERE username = $2";
+        await this.query(query, [role, username]);
     }
 
     async getUserLang(username: string) {
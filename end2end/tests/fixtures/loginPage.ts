@@ -1,4 +1,4 @@
-import {expect, Locator, Page} from '@playwright/test';
+import {Browser, expect, Locator, Page} from '@playwright/test';
 import {I18n, test as base} from "./i18n";
 
 const LOGIN_URL_PATTERN = /\/login\?orig_url=.*/;
@@ -9,6 +9,7 @@ class LoginPage {
     private readonly usernameInput: Locator;
     private readonly passwordInput;
     private readonly loginButton: Locator;
+    private readonly logoutButton: Locator;
     readonly heading: Locator;
     readonly langSelect: Locator;
 
@@ -21,6 +22,10 @@ class LoginPage {
         this.usernameInput = page.getByRole('textbox', {name: usernameText});
         this.passwordInput = page.getByRole('textbox', {name: passwordText});
         this.loginButton = page.getByRole('button', {name: loginText});
+        // the user's lang may differ from the browser lang after logging in
+        const logoutText = new RegExp(
+            `^(${i18nHelper.get("en", "logout")}|${i18nHelper.get("de", "logout")})$`);
+        this.logoutButton = page.getByRole("navigation").getByRole('button', {name: logoutText});
         this.heading = page.getByRole('heading');
         this.langSelect = page.getByRole("navigation").getByLabel("Language");
     }
@@ -37,6 +42,25 @@ class LoginPage {
         await this.loginButton.click();
     }
 
+    // logs in on a second "device", whose browser lang may differ from this page's
+    async loginInNewContext(browser: Browser, username: string, password = 'password'): Promise<Page> {
+        const bothLangs = (key: string) =>
+            `${this.i18nHelper.get("en", key)}|${this.i18nHelper.get("de", key)}`;
+        const context = await browser.newContext();
+        const page = await context.newPage();
+        await page.goto("/");
+        await page.getByRole('textbox', {name: new RegExp(bothLangs("username"))}).fill(username);
+        await page.getByRole('textbox', {name: new RegExp(bothLangs("password"))}).fill(password);
+        await page.getByRole('button', {name: new RegExp(`^(${bothLangs("login")})$`)}).click();
+        await expect(page).toHaveURL("/");
+        return page;
+    }
+
+    async logout() {
+        await this.logoutButton.click();
+        await expect(this.page).toHaveURL("/login");
+    }
+
     async setLang(selectName: string) {
         await this.langSelect.selectOption(selectName);
     }
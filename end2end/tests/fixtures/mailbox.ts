@@ -0,0 +1,64 @@
+import {expect, test as base} from '@playwright/test';
+import * as fs from "node:fs/promises";
+import * as path from "node:path";
+
+// The test configuration has to use the "directory" mail transport
+interface TestConfig {
+    mail: {
+        transport: {
+            type: string;
+            path: string;
+        };
+    };
+}
+
+// Keep in sync with TestConfig interface above
+function isTestConfig(obj: any): obj is TestConfig {
+    return obj?.mail?.transport?.type === 'directory'
+        && typeof obj?.mail?.transport?.path === 'string';
+}
+
+export class Mailbox {
+    private mailPath: string | undefined;
+
+    async init() {
+        const configFile = await fs.readFile('../config/configuration.test.json');
+        const raw = JSON.parse(configFile.toString());
+        if (!isTestConfig(raw)) throw new Error("Test configuration has no mail directory");
+        const config: TestConfig = raw;
+        // the path is relative to the server's pwd, the project root
+        this.mailPath = path.join("..", config.mail.transport.path);
+    }
+
+    // Waits for the latest mail to the given address, as the server sends mails in the background
+    async latestMailTo(address: string) {
+        if (!this.mailPath) {
+            throw new Error("Run init() before using latestMailTo()!");
+        }
+        const mailPath = this.mailPath;
+        let mail = "";
+        await expect(async () => {
+            const files = (await fs.readdir(mailPath)).sort().reverse();
+            for (const file of files) {
+                const content = (await fs.readFile(path.join(mailPath, file))).toString();
+                if (content.includes(`To: ${address}\n`)) {
+                    mail = content;
+                    return;
+                }
+            }
+            throw new Error(`No mail to ${address} yet`);
+        }).toPass();
+        return mail;
+    }
+}
+
+// noinspection JSVoidFunctionReturnValueUsed
+export const test = base.extend<{}, { mailbox: Mailbox }>({
+    mailbox: [
+        async ({}, use) => {
+            const mailbox = new Mailbox();
+            await mailbox.init();
+            await use(mailbox);
+        }, {scope: 'worker'}
+    ],
+});
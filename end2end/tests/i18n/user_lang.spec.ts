@@ -31,7 +31,7 @@ test.describe("browser lang is english", async () => {
     });
 
     test('setting the lang as user is persisted in db and shown on next login',
-        async ({page, dbHelper, loginPage, browser}) => {
+        async ({page, dbHelper, loginPage}) => {
             const username = await dbHelper.addTestUser('en');
             await loginPage.navigate();
             await loginPage.login(username);
@@ -40,14 +40,12 @@ test.describe("browser lang is english", async () => {
             await expect(page.getByRole('heading')).toHaveText(germanHomeTitle);
             await page.waitForTimeout(500);
             expect(await dbHelper.getUserLang(username)).toBe("de");
-            await browser.newContext();
+            await loginPage.logout();
             await page.evaluate(() => localStorage.removeItem('lang'));
             await loginPage.navigate();
             await expect(loginPage.heading).toHaveText(englishLoginTitle);
             await loginPage.login(username);
             await expect(page.getByRole('heading')).toHaveText(germanHomeTitle);
-            // @ts-ignore
-            await browser.contexts().pop().close();
             await dbHelper.deleteTestUser(username);
         });
 });
@@ -64,7 +62,7 @@ test.describe("browser lang is german", async () => {
     });
 
     test('setting the lang as user is persisted in db and shown on next login',
-        async ({page, dbHelper, loginPage, browser}) => {
+        async ({page, dbHelper, loginPage}) => {
             const username = await dbHelper.addTestUser('de');
             await loginPage.navigate();
             await loginPage.login(username);
@@ -73,14 +71,12 @@ test.describe("browser lang is german", async () => {
             await expect(page.getByRole('heading')).toHaveText(englishHomeTitle);
             await page.waitForTimeout(1500);
             expect(await dbHelper.getUserLang(username)).toBe("en");
-            await browser.newContext();
+            await loginPage.logout();
             await page.evaluate(() => localStorage.removeItem('lang'));
             await loginPage.navigate();
             await expect(loginPage.heading).toHaveText(germanLoginTitle);
             await loginPage.login(username);
             await expect(page.getByRole('heading')).toHaveText(englishHomeTitle);
-            // @ts-ignore
-            await browser.contexts().pop().close();
             await dbHelper.deleteTestUser(username);
         });
 });
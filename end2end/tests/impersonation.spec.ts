@@ -0,0 +1,36 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+const test = mergeTests(dbTest, lpTest);
+
+test('an admin impersonates a user and stops it again', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const admin = await dbHelper.addTestUser('en');
+    await dbHelper.setUserRole(admin, 'admin');
+    const username = await dbHelper.addTestUser('de');
+    const stopText = new RegExp(
+        `^(${i18nHelper.get("en", "stopImpersonating")}|${i18nHelper.get("de", "stopImpersonating")})$`);
+
+    await loginPage.navigate();
+    await loginPage.login(admin);
+    await expect(page).toHaveURL("/");
+    await page.goto("/admin/users");
+    await page.getByRole('searchbox').fill(username);
+    const row = page.getByRole('row').filter({hasText: username});
+    await row.getByRole('button', {name: i18nHelper.get("en", "impersonateAccount")}).click();
+    await expect(page).toHaveURL("/");
+    const stopButton = page.getByRole("navigation").getByRole('button', {name: stopText});
+    await expect(stopButton).toBeVisible();
+
+    // the impersonated user isn't an admin
+    await page.goto("/admin/users");
+    await expect(page.getByRole('heading', {name: i18nHelper.get("de", "forbidden")})).toBeVisible();
+
+    await stopButton.click();
+    await expect(page).toHaveURL("/admin/users");
+    await expect(page.getByRole('heading', {name: i18nHelper.get("en", "userManagement")})).toBeVisible();
+    await expect(stopButton).toHaveCount(0);
+
+    await dbHelper.deleteTestUser(username);
+    await dbHelper.deleteTestUser(admin);
+});
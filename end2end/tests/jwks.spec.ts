@@ -0,0 +1,14 @@
+import {expect, test} from '@playwright/test';
+
+test('the JWKS document is public and has no secrets', async ({request}) => {
+    const response = await request.get("/.well-known/jwks.json");
+    expect(response.ok()).toBeTruthy();
+    const jwks = await response.json();
+    expect(Array.isArray(jwks.keys)).toBeTruthy();
+    for (const key of jwks.keys) {
+        expect(typeof key.kid).toBe("string");
+        // neither HMAC secrets nor private key parts
+        expect(key.k).toBeUndefined();
+        expect(key.d).toBeUndefined();
+    }
+});
@@ -20,3 +20,43 @@ test('login works the bad way (wrong password)', async ({loginPage}) => {
     await loginPage.login(VALID_USERNAME, "12345678");
     await loginPage.expectInvalidCredentialsError();
 });
+
+test('logout works', async ({page, loginPage}) => {
+    await loginPage.navigate();
+    await loginPage.login(VALID_USERNAME);
+    await expect(page).toHaveURL("/");
+    await loginPage.logout();
+    expect(await page.evaluate(() => sessionStorage.getItem('token'))).toBeNull();
+    await page.goto("/");
+    await expect(page).toHaveURL(/\/login\?orig_url=.*/);
+});
+
+test('login survives a reload', async ({page, loginPage}) => {
+    await loginPage.navigate();
+    await loginPage.login(VALID_USERNAME);
+    await expect(page).toHaveURL("/");
+    await page.goto("/imprint");
+    await page.reload();
+    await expect(page).toHaveURL("/imprint");
+    await page.goto("/");
+    await expect(page).toHaveURL("/");
+    await loginPage.logout();
+});
+
+test('the session token carries the registered claims', async ({page, loginPage}) => {
+    await loginPage.navigate();
+    await loginPage.login(VALID_USERNAME);
+    await expect(page).toHaveURL("/");
+    const [token, expires] = await page.evaluate(
+        () => [sessionStorage.getItem('token'), sessionStorage.getItem('expires')]);
+    const header = JSON.parse(Buffer.from(token!.split(".")[0], "base64url").toString());
+    const claims = JSON.parse(Buffer.from(token!.split(".")[1], "base64url").toString());
+    // names the signing key, so it can be rotated
+    expect(typeof header.kid).toBe("string");
+    expect(typeof claims.iss).toBe("string");
+    expect(typeof claims.aud).toBe("string");
+    expect(claims.nbf).toBeLessThanOrEqual(claims.iat);
+    // the token expires together with the session
+    expect(claims.exp).toBe(Number(expires));
+    await loginPage.logout();
+});
@@ -0,0 +1,44 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests, Page} from '@playwright/test';
+
+// the provider is end2end/oidc-mock.mjs, see there for the test configuration
+const test = mergeTests(dbTest, lpTest);
+
+async function loginAtProvider(page: Page, username: string) {
+    await page.getByRole('textbox', {name: "Provider username"}).fill(username);
+    await page.getByRole('button', {name: "Sign in at provider"}).click();
+}
+
+test('single sign-on links an existing account by its username', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+
+    await loginPage.navigate();
+    await page.getByRole('button', {name: i18nHelper.get("en", "singleSignOn")}).click();
+    await loginAtProvider(page, username);
+    await expect(page).toHaveURL("/");
+    await expect(page.getByRole("navigation")).toContainText("Test User");
+
+    await dbHelper.deleteTestUser(username);
+});
+
+test('single sign-on creates an account for an unknown user', async ({page, dbHelper, i18nHelper}) => {
+    const username = "sso_testuser_" + test.info().workerIndex;
+    // left over, if a previous run failed
+    await dbHelper.deleteTestUser(username);
+
+    await page.goto("/profile");
+    await expect(page).toHaveURL(/\/login\?orig_url=.*/);
+    await page.getByRole('button', {name: i18nHelper.get("en", "singleSignOn")}).click();
+    await loginAtProvider(page, username);
+    await expect(page).toHaveURL("/profile");
+    await expect(page.getByRole("navigation")).toContainText(`SSO ${username}`);
+
+    await dbHelper.deleteTestUser(username);
+});
+
+test('a callback with an unknown state is rejected', async ({page, i18nHelper}) => {
+    await page.goto("/oidc-callback?code=abc&state=def");
+    await expect(page.getByText(i18nHelper.get("en", "singleSignOnFailed")!)).toBeVisible();
+    expect(await page.evaluate(() => sessionStorage.getItem('token'))).toBeNull();
+});
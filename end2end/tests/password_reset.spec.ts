@@ -0,0 +1,44 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {test as mbTest} from './fixtures/mailbox';
+import {expect, mergeTests} from '@playwright/test';
+
+const test = mergeTests(dbTest, lpTest, mbTest);
+
+test('password can be reset by mail', async ({page, dbHelper, loginPage, mailbox, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+    const email = username + "@example.org";
+    await dbHelper.setUserEmail(username, email);
+    const newPassword = "new password";
+
+    await loginPage.navigate();
+    await page.getByRole('link', {name: /^(Forgot your password\?|Passwort vergessen\?)$/}).click();
+    await expect(page).toHaveURL("/forgot-password");
+    await page.getByRole('textbox', {name: /Username|Benutzername/}).fill(username);
+    await page.getByRole('button', {name: /^(Send reset link|Link senden)$/}).click();
+    await expect(page.getByText(/(reset link has been sent|Link zum Zurücksetzen gesendet)/)).toBeVisible();
+
+    const mail = await mailbox.latestMailTo(email);
+    const link = mail.match(/\/reset-password\?token=[0-9a-f]{64}/);
+    expect(link).not.toBeNull();
+    await page.goto(link![0]);
+    await page.getByRole('textbox', {name: /^(New password|Neues Passwort)$/}).fill(newPassword);
+    await page.getByRole('textbox', {name: /^(Repeat new password|Neues Passwort wiederholen)$/}).fill(newPassword);
+    await page.getByRole('button', {name: /^(Reset Password|Passwort zurücksetzen)$/}).click();
+    await expect(page.getByText(i18nHelper.get("en", "passwordResetDone"))
+        .or(page.getByText(i18nHelper.get("de", "passwordResetDone")))).toBeVisible();
+
+    // the link can only be used once
+    await page.goto(link![0]);
+    await page.getByRole('textbox', {name: /^(New password|Neues Passwort)$/}).fill(newPassword);
+    await page.getByRole('textbox', {name: /^(Repeat new password|Neues Passwort wiederholen)$/}).fill(newPassword);
+    await page.getByRole('button', {name: /^(Reset Password|Passwort zurücksetzen)$/}).click();
+    await expect(page.getByText(i18nHelper.get("en", "resetLinkInvalid"))
+        .or(page.getByText(i18nHelper.get("de", "resetLinkInvalid")))).toBeVisible();
+
+    await loginPage.navigate();
+    await loginPage.login(username, newPassword);
+    await expect(page).toHaveURL("/");
+
+    await dbHelper.deleteTestUser(username);
+});
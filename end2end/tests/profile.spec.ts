@@ -0,0 +1,33 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+const test = mergeTests(dbTest, lpTest);
+
+test('name and language are changed on the profile page', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+    const noPreviousLoginText = i18nHelper.get("en", "noPreviousLogin");
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/profile");
+    await expect(page.getByText(noPreviousLoginText)).toBeVisible();
+
+    // the previous login is shown from the second login on
+    await loginPage.logout();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/profile");
+    await expect(page.getByText(i18nHelper.get("en", "memberSince"))).toBeVisible();
+    await expect(page.getByText(noPreviousLoginText)).toHaveCount(0);
+
+    await page.getByRole('textbox', {name: i18nHelper.get("en", "name"), exact: true}).fill("Renamed User");
+    await page.getByRole('combobox', {name: i18nHelper.get("en", "preferred")}).selectOption("De");
+    await page.getByRole('button', {name: i18nHelper.get("en", "saveProfile")}).click();
+    await expect(page.getByText(i18nHelper.get("de", "profileSaved"))).toBeVisible();
+    await expect(page.getByRole("navigation").getByText("Renamed User")).toBeVisible();
+    expect(await dbHelper.getUserLang(username)).toBe("de");
+
+    await dbHelper.deleteTestUser(username);
+});
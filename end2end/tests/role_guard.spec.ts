@@ -0,0 +1,66 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests, Page, Request} from '@playwright/test';
+
+const test = mergeTests(dbTest, lpTest);
+
+// records the call of `list_accounts` by the admin page
+async function recordAdminServerFnCall(page: Page): Promise<Request> {
+    const serverFnCall = page.waitForRequest((request: Request) =>
+        request.url().includes("/api/list_accounts"));
+    await page.goto("/admin/users");
+    return serverFnCall;
+}
+
+test('the admin link is only shown to admins', async ({page, browser, dbHelper, loginPage, i18nHelper}) => {
+    const admin = await dbHelper.addTestUser('en');
+    await dbHelper.setUserRole(admin, 'admin');
+    const username = await dbHelper.addTestUser('de');
+    const userManagementText = new RegExp(
+        `^(${i18nHelper.get("en", "userManagement")}|${i18nHelper.get("de", "userManagement")})$`);
+
+    const adminPage = await loginPage.loginInNewContext(browser, admin);
+    await expect(adminPage.getByRole("navigation").getByRole('link', {name: userManagementText})).toBeVisible();
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await expect(page.getByRole("navigation").getByRole('link', {name: userManagementText})).toBeHidden();
+
+    await adminPage.context().close();
+    await dbHelper.deleteTestUser(username);
+    await dbHelper.deleteTestUser(admin);
+});
+
+test('admin server fns are forbidden for other users', async ({page, browser, dbHelper, loginPage}) => {
+    const admin = await dbHelper.addTestUser('en');
+    await dbHelper.setUserRole(admin, 'admin');
+    const username = await dbHelper.addTestUser('de');
+
+    const adminPage = await loginPage.loginInNewContext(browser, admin);
+    const adminCall = await recordAdminServerFnCall(adminPage);
+
+    // replays the admin's call with the session of the user
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    const userCall = page.waitForRequest((request: Request) =>
+        request.url().includes("/api/") && request.headers()["authorization"] !== undefined);
+    await page.reload();
+    const response = await page.request.post(adminCall.url(), {
+        headers: await (await userCall).allHeaders(),
+        data: adminCall.postData() ?? "",
+    });
+    expect(await response.text()).toContain("Forbidden");
+
+    // the admin's own call is still allowed
+    const adminResponse = await adminPage.request.post(adminCall.url(), {
+        headers: await adminCall.allHeaders(),
+        data: adminCall.postData() ?? "",
+    });
+    expect(await adminResponse.text()).not.toContain("Forbidden");
+
+    await adminPage.context().close();
+    await dbHelper.deleteTestUser(username);
+    await dbHelper.deleteTestUser(admin);
+});
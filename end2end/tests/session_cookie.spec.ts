@@ -0,0 +1,63 @@
+import {expect} from '@playwright/test';
+import {test} from "./fixtures/loginPage";
+
+// The test configuration has to send the session token both ways, so the other specs still
+// find it in the session storage:
+//   "jwt": {..., "transport": "both"}
+const VALID_USERNAME = "admin";
+const SESSION_COOKIE = "lsf_session";
+
+// WebKit doesn't store `Secure` cookies for http://localhost
+test.skip(({browserName}) => browserName === 'webkit', 'Secure cookies need https in WebKit');
+
+test('login sets an HttpOnly session cookie', async ({page, loginPage}) => {
+    await loginPage.navigate();
+    await loginPage.login(VALID_USERNAME);
+    await expect(page).toHaveURL("/");
+
+    const cookie = (await page.context().cookies()).find((cookie) => cookie.name === SESSION_COOKIE);
+    expect(cookie).toBeDefined();
+    expect(cookie!.httpOnly).toBe(true);
+    expect(cookie!.secure).toBe(true);
+    expect(cookie!.sameSite).toBe("Strict");
+    expect(await page.evaluate(() => document.cookie)).not.toContain(SESSION_COOKIE);
+});
+
+test('a new tab shares the session of the cookie', async ({page, loginPage}) => {
+    await loginPage.navigate();
+    await loginPage.login(VALID_USERNAME);
+    await expect(page).toHaveURL("/");
+
+    const newTab = await page.context().newPage();
+    await newTab.goto("/profile");
+    await expect(newTab).toHaveURL("/profile");
+});
+
+test('logout clears the session cookie', async ({page, loginPage}) => {
+    await loginPage.navigate();
+    await loginPage.login(VALID_USERNAME);
+    await expect(page).toHaveURL("/");
+    await loginPage.logout();
+
+    const cookies = await page.context().cookies();
+    expect(cookies.find((cookie) => cookie.name === SESSION_COOKIE)).toBeUndefined();
+    const newTab = await page.context().newPage();
+    await newTab.goto("/profile");
+    await expect(newTab).toHaveURL(/\/login\?orig_url=.*/);
+});
+
+test('an invalid session cookie is cleared', async ({page}) => {
+    await page.context().addCookies([{
+        name: SESSION_COOKIE,
+        value: "invalid",
+        url: "http://localhost:3456",
+        httpOnly: true,
+        secure: true,
+        sameSite: "Strict",
+    }]);
+    await page.goto("/profile");
+    await expect(page).toHaveURL(/\/login\?orig_url=.*/);
+
+    const cookies = await page.context().cookies();
+    expect(cookies.find((cookie) => cookie.name === SESSION_COOKIE)).toBeUndefined();
+});
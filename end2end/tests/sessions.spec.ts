@@ -0,0 +1,28 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+const test = mergeTests(dbTest, lpTest);
+
+test('other sessions are listed and can be ended', async ({page, browser, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+    const currentSessionText = i18nHelper.get("en", "currentSession");
+    const revokeSessionText = i18nHelper.get("en", "revokeSession");
+    const revokeOtherSessionsText = i18nHelper.get("en", "revokeOtherSessions");
+
+    const otherPage = await loginPage.loginInNewContext(browser, username);
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/sessions");
+    await expect(page.getByText(currentSessionText)).toBeVisible();
+    await expect(page.getByRole('button', {name: revokeSessionText})).toHaveCount(1);
+
+    await page.getByRole('button', {name: revokeOtherSessionsText}).click();
+    await expect(page.getByRole('button', {name: revokeSessionText})).toHaveCount(0);
+    await expect(page.getByText(currentSessionText)).toBeVisible();
+
+    await otherPage.context().close();
+    await dbHelper.deleteTestUser(username);
+});
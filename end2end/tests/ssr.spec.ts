@@ -0,0 +1,74 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+
+// Pages are rendered for the session cookie, so the test configuration has to set it:
+//   "jwt": {..., "transport": "both"}
+const test = mergeTests(dbTest, lpTest);
+
+// WebKit doesn't store `Secure` cookies for http://localhost
+test.skip(({browserName}) => browserName === 'webkit', 'Secure cookies need https in WebKit');
+
+test('protected pages are rendered on the server in the preferred language', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('de');
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+
+    // the html as rendered by the server, before any hydration
+    const response = await page.request.get("/profile", {headers: {Accept: "text/html"}});
+    const html = await response.text();
+    expect(html).toContain("Test User");
+    expect(html).toContain(i18nHelper.get("de", "changePassword"));
+
+    await dbHelper.deleteTestUser(username);
+});
+
+test('protected pages render without scripts', async ({browser, page, dbHelper, loginPage}) => {
+    const username = await dbHelper.addTestUser('en');
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+
+    // without scripts, only the server can render the page
+    const context = await browser.newContext({javaScriptEnabled: false});
+    await context.addCookies(await page.context().cookies());
+    const serverRenderedPage = await context.newPage();
+    await serverRenderedPage.goto("/profile");
+    await expect(serverRenderedPage.getByRole("navigation")).toContainText("Test User");
+    await context.close();
+
+    await dbHelper.deleteTestUser(username);
+});
+
+test('forms of a session are posted without scripts', async ({browser, page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+
+    // without scripts, the form carries the CSRF token instead of the header
+    const context = await browser.newContext({javaScriptEnabled: false});
+    await context.addCookies(await page.context().cookies());
+    const serverRenderedPage = await context.newPage();
+    await serverRenderedPage.goto("/change-password");
+    await serverRenderedPage.getByLabel(i18nHelper.get("en", "currentPassword"), {exact: true}).fill("password");
+    await serverRenderedPage.getByLabel(i18nHelper.get("en", "newPassword"), {exact: true}).fill("new password");
+    await serverRenderedPage.getByLabel(i18nHelper.get("en", "newPasswordRepeated"), {exact: true}).fill("new password");
+    await serverRenderedPage.getByRole('button', {name: i18nHelper.get("en", "changePassword")}).click();
+    await serverRenderedPage.waitForLoadState();
+    await context.close();
+
+    // a forbidden post would have left the password unchanged
+    await loginPage.logout();
+    await loginPage.login(username, "new password");
+    await expect(page).toHaveURL("/");
+
+    await dbHelper.deleteTestUser(username);
+});
+
+test('anonymous requests render no protected content', async ({page, i18nHelper}) => {
+    const response = await page.request.get("/profile", {headers: {Accept: "text/html"}});
+    const html = await response.text();
+    expect(html).not.toContain(i18nHelper.get("en", "changePassword"));
+});
@@ -0,0 +1,56 @@
+import {test as dbTest} from './fixtures/database';
+import {test as lpTest} from './fixtures/loginPage';
+import {expect, mergeTests} from '@playwright/test';
+import {createHmac} from 'crypto';
+
+const test = mergeTests(dbTest, lpTest);
+
+// RFC 6238 code with the defaults the server uses: SHA1, 6 digits, 30 seconds
+function totpCode(base32Secret: string): string {
+    const alphabet = "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let bits = "";
+    for (const char of base32Secret.replace(/=+$/, "")) {
+        bits += alphabet.indexOf(char).toString(2).padStart(5, "0");
+    }
+    const key = Buffer.from(bits.match(/.{8}/g)!.map(byte => parseInt(byte, 2)));
+    const counter = Buffer.alloc(8);
+    counter.writeBigUInt64BE(BigInt(Math.floor(Date.now() / 30000)));
+    const hmac = createHmac("sha1", key).update(counter).digest();
+    const offset = hmac[hmac.length - 1] & 0xf;
+    return ((hmac.readUInt32BE(offset) & 0x7fffffff) % 1000000).toString().padStart(6, "0");
+}
+
+test('two-factor login with a recovery code', async ({page, dbHelper, loginPage, i18nHelper}) => {
+    const username = await dbHelper.addTestUser('en');
+    const both = (key: string) =>
+        new RegExp(`^(${i18nHelper.get("en", key)}|${i18nHelper.get("de", key)})$`);
+
+    await loginPage.navigate();
+    await loginPage.login(username);
+    await expect(page).toHaveURL("/");
+    await page.goto("/two-factor");
+    await page.getByRole('button', {name: both("enableTwoFactor")}).click();
+    await expect(page.getByRole('img')).toBeVisible();
+    const secret = await page.locator("code").textContent();
+    await page.getByRole('textbox', {name: both("totpCode")}).fill(totpCode(secret!));
+    await page.getByRole('button', {name: both("enableTwoFactor")}).click();
+    const recoveryCodes = page.locator("li");
+    await expect(recoveryCodes).toHaveCount(10);
+    const recoveryCode = await recoveryCodes.first().textContent();
+
+    await loginPage.logout();
+    await loginPage.login(username);
+    await expect(page.getByText(both("totpLoginIntro"))).toBeVisible();
+    await page.getByRole('textbox', {name: both("totpCode")}).fill(recoveryCode!);
+    await page.getByRole('button', {name: both("verifyTotp")}).click();
+    await expect(page).toHaveURL("/");
+
+    // a recovery code can only be used once
+    await loginPage.logout();
+    await loginPage.login(username);
+    await page.getByRole('textbox', {name: both("totpCode")}).fill(recoveryCode!);
+    await page.getByRole('button', {name: both("verifyTotp")}).click();
+    await expect(page.getByText(both("invalidTotpCode"))).toBeVisible();
+
+    await dbHelper.deleteTestUser(username);
+});
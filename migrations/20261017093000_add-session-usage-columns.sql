@@ -0,0 +1,7 @@
+ALTER TABLE public.session
+    ADD COLUMN created_at   TIMESTAMP DEFAULT current_timestamp NOT NULL,
+    ADD COLUMN last_used_at TIMESTAMP DEFAULT current_timestamp NOT NULL,
+    ADD COLUMN user_agent   varchar(255);
+
+CREATE INDEX session_account_id_index
+    ON public.session (account_id);
@@ -0,0 +1,4 @@
+ALTER TABLE public.account
+    ADD COLUMN failed_logins             integer DEFAULT 0 NOT NULL,
+    ADD COLUMN failed_login_window_start TIMESTAMP,
+    ADD COLUMN locked_until              TIMESTAMP;
@@ -0,0 +1,23 @@
+ALTER TABLE public.account
+    ADD COLUMN email varchar(254);
+
+CREATE TABLE public.password_reset_token
+(
+    id         UUID      DEFAULT gen_random_uuid() NOT NULL
+        CONSTRAINT password_reset_token_pk
+            PRIMARY KEY,
+    account_id UUID                                NOT NULL
+        CONSTRAINT password_reset_token_account_id_fk
+            REFERENCES public.account
+            ON DELETE CASCADE,
+    token_hash varchar(64)                         NOT NULL,
+    created_at TIMESTAMP DEFAULT current_timestamp NOT NULL,
+    expires_at TIMESTAMP                           NOT NULL,
+    used_at    TIMESTAMP
+);
+
+CREATE UNIQUE INDEX password_reset_token_token_hash_uindex
+    ON public.password_reset_token (token_hash);
+
+CREATE INDEX password_reset_token_account_id_index
+    ON public.password_reset_token (account_id);
@@ -0,0 +1,36 @@
+ALTER TABLE public.account
+    ADD COLUMN totp_secret         varchar(64),
+    ADD COLUMN totp_enabled_at     TIMESTAMP,
+    ADD COLUMN totp_last_used_step bigint;
+
+CREATE TABLE public.totp_recovery_code
+(
+    id         UUID DEFAULT gen_random_uuid() NOT NULL
+        CONSTRAINT totp_recovery_code_pk
+            PRIMARY KEY,
+    account_id UUID                           NOT NULL
+        CONSTRAINT totp_recovery_code_account_id_fk
+            REFERENCES public.account
+            ON DELETE CASCADE,
+    code_hash  varchar(64)                    NOT NULL,
+    used_at    TIMESTAMP
+);
+
+CREATE INDEX totp_recovery_code_account_id_index
+    ON public.totp_recovery_code (account_id);
+
+CREATE TABLE public.login_challenge
+(
+    id         UUID DEFAULT gen_random_uuid() NOT NULL
+        CONSTRAINT login_challenge_pk
+            PRIMARY KEY,
+    account_id UUID                           NOT NULL
+        CONSTRAINT login_challenge_account_id_fk
+            REFERENCES public.account
+            ON DELETE CASCADE,
+    token_hash varchar(64)                    NOT NULL,
+    expires_at TIMESTAMP                      NOT NULL
+);
+
+CREATE UNIQUE INDEX login_challenge_token_hash_uindex
+    ON public.login_challenge (token_hash);
@@ -0,0 +1,9 @@
+CREATE TYPE role AS ENUM ('user', 'admin');
+
+ALTER TABLE public.account
+    ADD role role DEFAULT 'user' NOT NULL;
+
+-- the account inserted by 20251121200527_insert-admin-account.sql
+UPDATE public.account
+SET role = 'admin'
+WHERE username = 'admin';
@@ -0,0 +1,4 @@
+ALTER TABLE public.account
+    ADD COLUMN created_at        TIMESTAMP DEFAULT current_timestamp NOT NULL,
+    ADD COLUMN last_login_at     TIMESTAMP,
+    ADD COLUMN previous_login_at TIMESTAMP;
@@ -0,0 +1,5 @@
+ALTER TABLE public.session
+    ADD COLUMN impersonator_id UUID
+        CONSTRAINT session_impersonator_id_fk
+            REFERENCES public.account
+            ON DELETE CASCADE;
@@ -0,0 +1,21 @@
+CREATE TABLE public.api_token
+(
+    id           UUID      DEFAULT gen_random_uuid() NOT NULL
+        CONSTRAINT api_token_pk
+            PRIMARY KEY,
+    account_id   UUID                                NOT NULL
+        CONSTRAINT api_token_account_id_fk
+            REFERENCES public.account
+            ON DELETE CASCADE,
+    name         varchar(50)                         NOT NULL,
+    token_hash   varchar(64)                         NOT NULL,
+    created_at   TIMESTAMP DEFAULT current_timestamp NOT NULL,
+    expires_at   TIMESTAMP,
+    last_used_at TIMESTAMP
+);
+
+CREATE UNIQUE INDEX api_token_token_hash_uindex
+    ON public.api_token (token_hash);
+
+CREATE INDEX api_token_account_id_index
+    ON public.api_token (account_id);
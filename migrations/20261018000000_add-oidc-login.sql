@@ -0,0 +1,20 @@
+ALTER TABLE public.account
+    ADD COLUMN oidc_subject varchar(255);
+
+CREATE UNIQUE INDEX account_oidc_subject_uindex
+    ON public.account (oidc_subject);
+
+CREATE TABLE public.oidc_login
+(
+    id            UUID      DEFAULT gen_random_uuid() NOT NULL
+        CONSTRAINT oidc_login_pk
+            PRIMARY KEY,
+    state_hash    varchar(64)                         NOT NULL,
+    code_verifier varchar(64)                         NOT NULL,
+    nonce         varchar(64)                         NOT NULL,
+    orig_url      varchar(255)                        NOT NULL,
+    expires_at    TIMESTAMP                           NOT NULL
+);
+
+CREATE UNIQUE INDEX oidc_login_state_hash_uindex
+    ON public.oidc_login (state_hash);
@@ -0,0 +1,5 @@
+-- sessions without a CSRF token couldn't call server fns anymore, so they have to log in again
+DELETE FROM public.session;
+
+ALTER TABLE public.session
+    ADD COLUMN csrf_token_hash varchar(64) NOT NULL;
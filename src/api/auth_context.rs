@@ -0,0 +1,144 @@
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::model::role::Role;
+use actix_web::{HttpMessage, HttpRequest};
+use log::{log, Level};
+use sqlx::types::Uuid;
+
+/// The authenticated session of a request, inserted by the `Authorisation` middleware.
+///
+/// # Example
+/// In a leptos server fn:
+/// ```ignore
+/// #[server(client = crate::client::AddAuthHeaderClient)]
+/// pub async fn foo() -> Result<ApiResponse<()>, ServerFnError> {
+///     let auth = match AuthContext::extract().await {
+///         Err(err) => return return_early(err),
+///         Ok(auth) => auth,
+///     };
+///     // use auth.account_id etc. as required
+///     Ok(auth.response(()))
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AuthContext {
+    pub account_id: Uuid,
+    /// The session, or the API token if `via_api_token` is set.
+    pub session_id: Uuid,
+    /// Whether the request was authorised with a personal API token instead of a session JWT.
+    pub via_api_token: bool,
+    pub role: Role,
+    /// The admin who started the session to impersonate the account, if any.
+    pub impersonator_id: Option<Uuid>,
+    /// The JWT to continue the session with, renewed as the session has been extended.
+    ///
+    /// For API tokens, it's the API token itself. It's empty if the session token is only sent
+    /// as cookie, see `SessionTransport`.
+    pub token: String,
+    /// The expiry of the session as UTC timestamp, already extended by this request.
+    ///
+    /// API tokens don't slide, it's their fixed expiry, or 0 if they don't expire.
+    pub expires_at: i64,
+}
+
+impl AuthContext {
+    /// Gets the context of the current request inside a server fn.
+    ///
+    /// # Returns
+    /// - `Err(ApiError::Unauthorized)`: If the request hasn't been authorised by the middleware,
+    ///   e.g. in a public server fn.
+    pub async fn extract() -> Result<Self, ApiError> {
+        let req: HttpRequest = match leptos_actix::extract().await {
+            Err(err) => {
+                log!(Level::Error, "Couldn't extract request: {}", err);
+                return Err(ApiError::Unauthorized);
+            }
+            Ok(req) => req,
+        };
+        Self::from_request(&req)
+    }
+
+    pub fn from_request(req: &HttpRequest) -> Result<Self, ApiError> {
+        match req.extensions().get::<AuthContext>() {
+            None => {
+                log!(Level::Warn, "No auth context for {}", req.path());
+                Err(ApiError::Unauthorized)
+            }
+            Some(auth) => Ok(auth.clone()),
+        }
+    }
+
+    /// Checks the role for server fns needing more than a login, before doing anything else.
+    ///
+    /// # Returns
+    /// - `Err(ApiError::Forbidden)`: If the account's role doesn't include the `required` one.
+    pub fn require_role(&self, required: Role) -> Result<(), ApiError> {
+        if self.role.includes(required) {
+            Ok(())
+        } else {
+            log!(
+                Level::Warn,
+                "Forbidden call by account {} with role {}, needs {}",
+                self.account_id,
+                self.role,
+                required
+            );
+            Err(ApiError::Forbidden)
+        }
+    }
+
+    /// Checks that the request comes from a login session, before managing credentials.
+    ///
+    /// A leaked API token mustn't be usable to keep or widen the access.
+    ///
+    /// # Returns
+    /// - `Err(ApiError::Forbidden)`: If the request was authorised with an API token.
+    pub fn require_session(&self) -> Result<(), ApiError> {
+        if self.via_api_token {
+            log!(
+                Level::Warn,
+                "Forbidden call with API token {} of account {}",
+                self.session_id,
+                self.account_id
+            );
+            Err(ApiError::Forbidden)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Checks that the request comes from the account holder's own login session, before
+    /// creating or changing credentials.
+    ///
+    /// An impersonating admin mustn't leave credentials behind, they would outlive the
+    /// impersonation and couldn't be traced back to it.
+    ///
+    /// # Returns
+    /// - `Err(ApiError::Forbidden)`: If the request was authorised with an API token or comes
+    ///   from an impersonation.
+    pub fn require_own_session(&self) -> Result<(), ApiError> {
+        self.require_session()?;
+        match self.impersonator_id {
+            None => Ok(()),
+            Some(impersonator_id) => {
+                log!(
+                    Level::Warn,
+                    "Forbidden call by account {} impersonating account {}",
+                    impersonator_id,
+                    self.account_id
+                );
+                Err(ApiError::Forbidden)
+            }
+        }
+    }
+
+    /// Wraps `data` into the response envelope with the session's token and new expiry.
+    pub fn response<T>(&self, data: T) -> ApiResponse<T> {
+        ApiResponse {
+            expires_at: self.expires_at,
+            token: self.token.clone(),
+            error: None,
+            data,
+        }
+    }
+}
@@ -11,6 +11,22 @@ pub enum ApiError {
     DBConnectionError,
     UnexpectedError(String),
     Expired,
+    /// The account is locked after too many failed logins until the given UTC timestamp.
+    AccountLocked(i64),
+    /// Too many requests, the client should retry after the given number of seconds.
+    RateLimited(u64),
+    /// The password reset token is unknown, expired or already used.
+    InvalidResetToken,
+    /// The TOTP or recovery code is wrong or has already been used.
+    InvalidTotpCode,
+    /// The account is authenticated, but its role doesn't allow the call.
+    Forbidden,
+    /// The account has been disabled by an admin.
+    AccountDisabled,
+    /// Another account already has the username.
+    UsernameTaken,
+    /// The login at the OpenID Connect provider failed or couldn't be linked to an account.
+    OidcLoginFailed,
 }
 
 impl Display for ApiError {
@@ -22,16 +38,26 @@ impl Display for ApiError {
             ApiError::UnexpectedError(err) => err.to_string(),
             ApiError::Expired => "Session expired".to_string(),
             &ApiError::DBConnectionError => "No DB connection".to_string(),
+            ApiError::AccountLocked(until) => format!("Account locked until {}", until),
+            ApiError::RateLimited(retry_after) => {
+                format!("Too many requests, retry after {} seconds", retry_after)
+            }
+            ApiError::InvalidResetToken => "Invalid or expired reset link".to_string(),
+            ApiError::InvalidTotpCode => "Invalid two-factor code".to_string(),
+            ApiError::Forbidden => "Forbidden".to_string(),
+            ApiError::AccountDisabled => "Account disabled".to_string(),
+            ApiError::UsernameTaken => "Username already taken".to_string(),
+            ApiError::OidcLoginFailed => "Single sign-on failed".to_string(),
         };
         write!(f, "{}", str)
     }
 }
 
-pub fn return_early(err: ApiError) -> Result<ApiResponse<()>, ServerFnError> {
+pub fn return_early<T: Default>(err: ApiError) -> Result<ApiResponse<T>, ServerFnError> {
     Ok(ApiResponse {
         error: Some(err),
         expires_at: 0,
         token: "".to_string(),
-        data: (),
+        data: T::default(),
     })
 }
@@ -1,116 +1,330 @@
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Validation};
+use crate::server_utils::configuration::{
+    JwtAlgorithm, JwtKeySettings, JwtSettings, SessionTransport,
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use ed25519_dalek::pkcs8::DecodePublicKey;
+use ed25519_dalek::VerifyingKey;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData,
+    Validation,
+};
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
 use serde::{Deserialize, Serialize};
 use sqlx::types::uuid::Error;
 use sqlx::types::Uuid;
-use std::collections::HashSet;
+use std::collections::HashMap;
+use std::fmt::Display;
 use std::str::FromStr;
 
+/// The payload of a session token.
+///
+/// Besides the session, it carries the registered claims, so a token is rejected outside its
+/// lifetime and deployment before the session is looked up.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct JwtClaim {
     session_id: String,
+    iss: String,
+    aud: String,
+    exp: i64,
+    iat: i64,
+    nbf: i64,
 }
 
+/// The keys of session tokens, identified by the `kid` header.
+///
+/// New tokens are signed with the key of `encode_kid`, tokens signed with any key in
+/// `decode_keys` are accepted. That includes the signing key and the verify-only keys.
 #[derive(Clone)]
 pub struct JwtKeys {
-    pub(crate) encode_key: EncodingKey,
-    pub(crate) decode_key: DecodingKey,
+    encode_kid: String,
+    encode_algorithm: Algorithm,
+    encode_key: EncodingKey,
+    decode_keys: HashMap<String, JwtDecodeKey>,
+    jwks: JwkSet,
+    /// The allowed clock skew in seconds.
+    pub(crate) leeway: u64,
+    /// How the tokens are sent, see `SessionTransport`.
+    pub(crate) transport: SessionTransport,
+    issuer: String,
+    audience: String,
 }
-impl JwtClaim {
-    pub fn new(session_id: Uuid) -> Self {
-        let session_id = session_id.to_string();
 
-        Self { session_id }
+/// A key only accepts tokens of its own algorithm, so a token can't pick another one.
+#[derive(Clone)]
+struct JwtDecodeKey {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+#[derive(Debug)]
+pub struct JwtKeysError(String);
+
+impl Display for JwtKeysError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
+}
 
+impl JwtClaim {
     pub fn try_into_uuid(self) -> Result<Uuid, Error> {
         Uuid::from_str(&self.session_id)
     }
+
+    /// Whether the token claims to be issued later than now, allowing for `leeway` seconds of
+    /// clock skew.
+    ///
+    /// `Validation` only checks `exp` and `nbf`, so `iat` is checked here.
+    pub fn is_issued_in_future(&self, leeway: u64) -> bool {
+        self.iat > Utc::now().timestamp() + leeway as i64
+    }
+}
+
+impl JwtKeys {
+    /// Encodes a token for `session_id`, which expires with the session at `expires_at`.
+    ///
+    /// As sessions slide, the `Authorisation` middleware encodes a new token for every request.
+    pub fn encode_token(
+        &self,
+        session_id: Uuid,
+        expires_at: i64,
+    ) -> Result<String, jsonwebtoken::errors::Error> {
+        let now = Utc::now().timestamp();
+        let claim = JwtClaim {
+            session_id: session_id.to_string(),
+            iss: self.issuer.clone(),
+            aud: self.audience.clone(),
+            exp: expires_at,
+            iat: now,
+            nbf: now,
+        };
+
+        let mut header = Header::new(self.encode_algorithm);
+        header.kid = Some(self.encode_kid.clone());
+
+        encode(&header, &claim, &self.encode_key)
+    }
+
+    /// Decodes and validates a token with the key its `kid` header names.
+    ///
+    /// Tokens without `kid` or with an unknown one are rejected as `ErrorKind::InvalidToken`,
+    /// e.g. those signed with a key removed by now.
+    pub fn decode_token(
+        &self,
+        token: &str,
+    ) -> Result<TokenData<JwtClaim>, jsonwebtoken::errors::Error> {
+        let decode_key = decode_header(token)?
+            .kid
+            .and_then(|kid| self.decode_keys.get(&kid))
+            .ok_or(ErrorKind::InvalidToken)?;
+
+        decode::<JwtClaim>(token, &decode_key.key, &decode_key.validation)
+    }
+
+    /// The public keys of the asymmetric keys as JWKS, HMAC secrets are never part of it.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
 }
 
 /// Generates a JWT validation configuration object.
 ///
 /// This function creates a `Validation` instance specifically configured
-/// for validating JWTs signed using the HMAC SHA-256 algorithm (`HS256`).
+/// for validating JWTs signed using the given algorithm, e.g., `HS256` or `EdDSA`.
 /// It modifies the default `Validation` object by making these adjustments:
 ///
-/// - Clears the set of required claims (`required_spec_claims`) to allow more flexibility.
-/// - Disables audience (`aud`) claim validation by setting `validate_aud` to `false`.
+/// - Requires the registered claims `exp`, `iat`, `nbf`, `iss` and `aud`.
+/// - Checks `iss` and `aud` against the configured issuer and audience.
+/// - Checks `exp` and `nbf` allowing for the configured leeway in seconds.
+///
+/// # Parameters
+/// - `settings`: The configured issuer, audience and leeway.
+/// - `algorithm`: The only algorithm accepted, the one of the key to decode with.
 ///
 /// # Returns
 ///
 /// A `Validation` object configured with the above settings.
 ///
-/// # Example
-///
-/// ```
-/// use jsonwebtoken::{Algorithm, Validation};
-///
-/// let validation = get_jwt_validation();
-///
-/// assert_eq!(validation.algorithms, vec![Algorithm::HS256]);
-/// assert!(validation.required_spec_claims.is_empty());
-/// assert!(!validation.validate_aud);
-/// ```
-///
-/// This can be used to validate incoming JWT tokens against the specific setup.
-///
 /// # Note
 ///
-/// - Make sure the returned `Validation` instance matches the requirements
-///   of your application's JWT validation logic.
-/// - Disabling `validate_aud` means that audience verification will not occur;
-///   this could reduce security if the audience claim is required by your use case.
-///
-/// # Dependencies
-///
-/// This function assumes you have imported:
-/// - `jsonwebtoken::{Algorithm, Validation}`
-/// - `std::collections::HashSet`
+/// - `Validation` doesn't check `iat` beyond its presence, see
+///   `JwtClaim::is_issued_in_future`.
 ///
 /// # See Also
 ///
 /// For more details, refer to the `jsonwebtoken` crate documentation:
 /// https://docs.rs/jsonwebtoken/latest/jsonwebtoken/
-pub fn get_jwt_validation() -> Validation {
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.required_spec_claims = HashSet::new();
-    validation.validate_aud = false;
+pub fn get_jwt_validation(settings: &JwtSettings, algorithm: Algorithm) -> Validation {
+    let mut validation = Validation::new(algorithm);
+    validation.set_required_spec_claims(&["exp", "iat", "nbf", "iss", "aud"]);
+    validation.set_issuer(&[&settings.issuer]);
+    validation.set_audience(&[&settings.audience]);
+    validation.validate_exp = true;
+    validation.validate_nbf = true;
+    validation.leeway = settings.leeway_secs;
 
     validation
 }
 
-/// Generates JWT (JSON Web Token) keys for encoding and decoding using the provided secret.
+/// Generates JWT (JSON Web Token) keys for encoding and decoding from the configured keys.
 ///
 /// # Parameters
-/// - `secret`: A `Vec<u8>` containing the secret key used to create
-///    both the encoding and decoding keys.
+/// - `settings`: The signing key, the verify-only keys, and the issuer and audience put into
+///   new tokens and checked by the validation.
 ///
 /// # Returns
-/// - `JwtKeys`: A structure containing:
-///     - `encode_key`: An `EncodingKey` generated from the provided secret,
-///       to be used for creating JWTs.
-///     - `decode_key`: A `DecodingKey` generated from the same secret,
-///       to be used for verifying and decoding JWTs.
+/// - `Ok(JwtKeys)`: A structure containing:
+///     - the `EncodingKey` of the signing key, to be used for creating JWTs.
+///     - a `DecodingKey` with the `Validation` of `get_jwt_validation` for each configured key
+///       by its `kid`, to be used for verifying and decoding JWTs.
+///     - the JWKS of the public keys.
+/// - `Err(JwtKeysError)`: If a `kid` is configured twice, or a key is missing or invalid.
 ///
 /// # Example
-/// ```
-/// let secret = b"my_secret_key".to_vec();
-/// let jwt_keys = get_jwt_keys(secret);
+/// ```ignore
+/// let jwt_keys = get_jwt_keys(&configuration.server.jwt)?;
 /// ```
 ///
 /// # Notes
-/// - The provided `secret` should be a secure and random sequence of bytes
+/// - The configured secrets should be secure and random sequences of bytes
 ///   to ensure the safety of the JWT tokens.
-/// - This function returns a `JwtKeys` struct that needs to implement
-///   to_owned for creating the owned instance of the keys.
+/// - The PEM files are read once, changed keys need a restart.
 ///
 /// # Dependencies
 /// Ensure the usage of the `jsonwebtoken` crate for accessing `EncodingKey`
 /// and `DecodingKey`.
-pub fn get_jwt_keys(secret: Vec<u8>) -> JwtKeys {
-    JwtKeys {
-        encode_key: EncodingKey::from_secret(secret.as_ref()),
-        decode_key: DecodingKey::from_secret(secret.as_ref()),
+pub fn get_jwt_keys(settings: &JwtSettings) -> Result<JwtKeys, JwtKeysError> {
+    let mut decode_keys = HashMap::new();
+    let mut jwks = JwkSet { keys: Vec::new() };
+    for key_settings in std::iter::once(&settings.signing_key).chain(&settings.verify_only_keys) {
+        let (key, jwk) = get_decoding_key(key_settings)?;
+        let decode_key = JwtDecodeKey {
+            key,
+            validation: get_jwt_validation(settings, to_algorithm(key_settings.algorithm)),
+        };
+        if decode_keys
+            .insert(key_settings.kid.clone(), decode_key)
+            .is_some()
+        {
+            return Err(JwtKeysError(format!(
+                "JWT key id configured twice: {}",
+                key_settings.kid
+            )));
+        }
+        jwks.keys.extend(jwk);
+    }
+
+    Ok(JwtKeys {
+        encode_kid: settings.signing_key.kid.clone(),
+        encode_algorithm: to_algorithm(settings.signing_key.algorithm),
+        encode_key: get_encoding_key(&settings.signing_key)?,
+        decode_keys,
+        jwks,
+        leeway: settings.leeway_secs,
+        transport: settings.transport,
+        issuer: settings.issuer.clone(),
+        audience: settings.audience.clone(),
+    })
+}
+
+fn to_algorithm(algorithm: JwtAlgorithm) -> Algorithm {
+    match algorithm {
+        JwtAlgorithm::HS256 => Algorithm::HS256,
+        JwtAlgorithm::EdDSA => Algorithm::EdDSA,
+        JwtAlgorithm::RS256 => Algorithm::RS256,
     }
-    .to_owned()
+}
+
+fn get_encoding_key(settings: &JwtKeySettings) -> Result<EncodingKey, JwtKeysError> {
+    match settings.algorithm {
+        JwtAlgorithm::HS256 => Ok(EncodingKey::from_secret(get_secret(settings)?)),
+        JwtAlgorithm::EdDSA => {
+            let pem = read_pem(settings, settings.private_key_path.as_ref())?;
+            EncodingKey::from_ed_pem(pem.as_bytes()).map_err(|err| invalid_key(settings, err))
+        }
+        JwtAlgorithm::RS256 => {
+            let pem = read_pem(settings, settings.private_key_path.as_ref())?;
+            EncodingKey::from_rsa_pem(pem.as_bytes()).map_err(|err| invalid_key(settings, err))
+        }
+    }
+}
+
+/// The key to verify tokens with, and its public JWK for asymmetric keys.
+fn get_decoding_key(settings: &JwtKeySettings) -> Result<(DecodingKey, Option<Jwk>), JwtKeysError> {
+    let common = CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_id: Some(settings.kid.clone()),
+        ..Default::default()
+    };
+    match settings.algorithm {
+        JwtAlgorithm::HS256 => Ok((DecodingKey::from_secret(get_secret(settings)?), None)),
+        JwtAlgorithm::EdDSA => {
+            let pem = read_pem(settings, settings.public_key_path.as_ref())?;
+            let key = DecodingKey::from_ed_pem(pem.as_bytes())
+                .map_err(|err| invalid_key(settings, err))?;
+            let public_key = VerifyingKey::from_public_key_pem(&pem)
+                .map_err(|err| invalid_key(settings, err))?;
+            let jwk = Jwk {
+                common: CommonParameters {
+                    key_algorithm: Some(KeyAlgorithm::EdDSA),
+                    ..common
+                },
+                algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                    key_type: OctetKeyPairType::OctetKeyPair,
+                    curve: EllipticCurve::Ed25519,
+                    x: URL_SAFE_NO_PAD.encode(public_key.as_bytes()),
+                }),
+            };
+            Ok((key, Some(jwk)))
+        }
+        JwtAlgorithm::RS256 => {
+            let pem = read_pem(settings, settings.public_key_path.as_ref())?;
+            let key = DecodingKey::from_rsa_pem(pem.as_bytes())
+                .map_err(|err| invalid_key(settings, err))?;
+            // `DecodePublicKey` is the same trait for both, re-exported from `pkcs8`
+            let public_key = RsaPublicKey::from_public_key_pem(&pem)
+                .map_err(|err| invalid_key(settings, err))?;
+            let jwk = Jwk {
+                common: CommonParameters {
+                    key_algorithm: Some(KeyAlgorithm::RS256),
+                    ..common
+                },
+                algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+                    key_type: RSAKeyType::RSA,
+                    n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+                    e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+                }),
+            };
+            Ok((key, Some(jwk)))
+        }
+    }
+}
+
+fn get_secret(settings: &JwtKeySettings) -> Result<&[u8], JwtKeysError> {
+    settings
+        .secret
+        .as_deref()
+        .ok_or_else(|| JwtKeysError(format!("JWT key {} has no secret", settings.kid)))
+}
+
+fn read_pem(settings: &JwtKeySettings, path: Option<&String>) -> Result<String, JwtKeysError> {
+    let path =
+        path.ok_or_else(|| JwtKeysError(format!("JWT key {} has no PEM file", settings.kid)))?;
+    std::fs::read_to_string(path).map_err(|err| {
+        JwtKeysError(format!(
+            "Couldn't read PEM file of JWT key {}: {}",
+            settings.kid, err
+        ))
+    })
+}
+
+fn invalid_key(settings: &JwtKeySettings, err: impl Display) -> JwtKeysError {
+    JwtKeysError(format!("Invalid JWT key {}: {}", settings.kid, err))
 }
@@ -1,4 +1,6 @@
 pub mod error;
 #[cfg(feature = "ssr")]
+pub mod auth_context;
+#[cfg(feature = "ssr")]
 pub mod jwt;
 pub mod response;
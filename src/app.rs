@@ -2,15 +2,33 @@ use crate::i18n::{use_i18n, Locale};
 use crate::layout::footer::Footer;
 use crate::layout::navbar::{NavBar, NavBarProps};
 use crate::layout::server_message::ServerMessage;
+use crate::layout::session_expiry::{SessionExpiryModal, SessionExpiryModalProps};
+use crate::model::role::Role;
 use crate::model::user::User;
+use crate::pages::admin_users::{AdminCreateUser, AdminEditUser, AdminUsers};
+use crate::pages::change_password::ChangePassword;
+use crate::pages::forbidden::Forbidden;
+use crate::pages::forgot_password::ForgotPassword;
 use crate::pages::home_page::HomePage;
 use crate::pages::imprint::Imprint;
-use crate::pages::login::{Login, LoginProps};
+use crate::pages::login::{get_user, Login, LoginProps};
 use crate::pages::not_found::NotFound;
+use crate::pages::oidc_login::{OidcCallback, OidcCallbackProps};
 use crate::pages::privacy::Privacy;
-use crate::utils::get_lang_from_browser;
+use crate::pages::profile::{ProfilePage, ProfilePageProps};
+use crate::pages::reset_password::ResetPassword;
+use crate::pages::sessions::Sessions;
+use crate::pages::two_factor::TwoFactor;
+use crate::utils::{
+    get_lang_from_browser, get_login_data_from_session_storage,
+    remove_login_data_from_session_storage, set_lang_to_i18n, set_login_data_to_session_storage,
+    set_user_lang,
+};
+use chrono::Utc;
 use leptos::html::{body, head, header, html, main};
 use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use leptos::server::SharedValue;
 use leptos::tachys::html::{doctype, InertElement};
 use leptos_i18n::context::{init_i18n_context_with_options, I18nContextOptions};
 use leptos_i18n::I18nContext;
@@ -24,6 +42,29 @@ use leptos_router::{
     StaticSegment, WildcardSegment,
 };
 
+/// Shows the page of `view` only to users whose role includes `role`, others get the
+/// forbidden page.
+///
+/// Meant for the view of a `ProtectedRoute`, which already waits for the user to be restored
+/// and redirects to the login page, e.g. `.view(move || with_role(Role::Admin, AdminPage))`.
+/// The server fns of such pages have to check the role with `AuthContext::require_role`, too.
+pub fn with_role<V, F>(role: Role, view: F) -> impl IntoView
+where
+    V: IntoView + 'static,
+    F: Fn() -> V + Send + Sync + 'static,
+{
+    let user = expect_context::<ReadSignal<Option<User>>>();
+
+    move || {
+        let has_role = user.get().is_some_and(|user| user.role.includes(role));
+        if has_role {
+            view().into_any()
+        } else {
+            Forbidden().into_any()
+        }
+    }
+}
+
 pub fn shell(options: LeptosOptions) -> impl IntoView {
     View::new((
         doctype("html"),
@@ -46,6 +87,21 @@ pub fn shell(options: LeptosOptions) -> impl IntoView {
     ))
 }
 
+/// Gets the user `PageAuthorisation` has inserted into the request, if any.
+fn get_ssr_user() -> Option<User> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::HttpMessage;
+
+        use_context::<actix_web::HttpRequest>()
+            .and_then(|req| req.extensions().get::<User>().cloned())
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        None
+    }
+}
+
 #[component]
 pub fn App() -> impl IntoView {
     // load, provide and initialize i18n context
@@ -59,6 +115,14 @@ pub fn App() -> impl IntoView {
     let i18n_signal = use_i18n();
     i18n_signal.set_locale(Locale::en);
 
+    // the user of the session cookie, resolved by `PageAuthorisation` for the server-side
+    // rendering and handed over to the hydration, so protected routes render on the server
+    let ssr_user = SharedValue::new(get_ssr_user).into_inner();
+    // only the locale, the lang of the client is still set by the restoring below
+    if let Some(ssr_user) = &ssr_user {
+        set_lang_to_i18n(&ssr_user.preferred_language);
+    }
+
     // initializing the global value lang needed by non-login pages
     // here the SSR lang is set, so for the reactiveness of the server message, it shouldn't match
     // any existing lang
@@ -67,8 +131,10 @@ pub fn App() -> impl IntoView {
     let browser_lang = move || get_lang_from_browser();
 
     // getting the lang from locale storage or browser settings
+    // a user rendered by the server keeps the preferred language until restored
+    let has_ssr_user = ssr_user.is_some();
     Effect::new(move || {
-        if browser_lang().is_some() {
+        if browser_lang().is_some() && !has_ssr_user {
             let browser_lang = if browser_lang().unwrap() == "en" {
                 "en"
             } else {
@@ -90,12 +156,38 @@ pub fn App() -> impl IntoView {
     provide_meta_context();
 
     // initializing and providing the user
-    let (user, set_user) = signal(None::<User>);
+    let (user, set_user) = signal(ssr_user.clone());
     provide_context(user);
 
+    // restoring the user from session storage after a reload, protected routes wait for it
+    // without stored login data, a session cookie of another tab may still be sent
+    // a user rendered by the server is shown meanwhile
+    let (is_restoring, set_restoring) = signal(ssr_user.is_none());
+    Effect::new(move || match get_login_data_from_session_storage() {
+        Some((_, expires)) if expires <= Utc::now().timestamp() => {
+            remove_login_data_from_session_storage();
+            set_restoring.set(false);
+        }
+        _ => {
+            spawn_local(async move {
+                match get_user().await {
+                    Ok(res) if res.error.is_none() => {
+                        set_login_data_to_session_storage(res.token.as_str(), res.expires_at);
+                        set_user_lang(&res.data.preferred_language, lang, set_lang);
+                        set_user.set(Some(res.data));
+                    }
+                    _ => remove_login_data_from_session_storage(),
+                }
+                set_restoring.set(false);
+            });
+        }
+    });
+
     // the guard for protected routes
     let is_logged_in = move || {
-        if user.get().is_some() {
+        if is_restoring.get() {
+            None
+        } else if user.get().is_some() {
             Some(true)
         } else {
             Some(false)
@@ -116,8 +208,12 @@ pub fn App() -> impl IntoView {
                 .children(ToChildren::to_children(move || {
                     (
                         {
-                            header()
-                                .child(NavBar(NavBarProps::builder().lang_setter(set_lang).build()))
+                            header().child(NavBar(
+                                NavBarProps::builder()
+                                    .lang_setter(set_lang)
+                                    .user_setter(set_user)
+                                    .build(),
+                            ))
                         },
                         { ServerMessage },
                         {
@@ -157,13 +253,152 @@ pub fn App() -> impl IntoView {
                                                         .build(),
                                                 )
                                             },
+                                            {
+                                                Route(
+                                                    RouteProps::builder()
+                                                        .path(StaticSegment("/oidc-callback"))
+                                                        .view(move || {
+                                                            OidcCallback(
+                                                                OidcCallbackProps::builder()
+                                                                    .set_user(set_user)
+                                                                    .lang_setter(set_lang)
+                                                                    .build(),
+                                                            )
+                                                        })
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                Route(
+                                                    RouteProps::builder()
+                                                        .path(StaticSegment("/forgot-password"))
+                                                        .view(ForgotPassword)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                Route(
+                                                    RouteProps::builder()
+                                                        .path(StaticSegment("/reset-password"))
+                                                        .view(ResetPassword)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                ProtectedRoute(
+                                                    ProtectedRouteProps::builder()
+                                                        .path(StaticSegment("/profile"))
+                                                        .view(move || {
+                                                            ProfilePage(
+                                                                ProfilePageProps::builder()
+                                                                    .set_user(set_user)
+                                                                    .lang_setter(set_lang)
+                                                                    .build(),
+                                                            )
+                                                        })
+                                                        .redirect_path(move || {
+                                                            "/login?orig_url=/profile"
+                                                        })
+                                                        .condition(is_logged_in)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                ProtectedRoute(
+                                                    ProtectedRouteProps::builder()
+                                                        .path(StaticSegment("/change-password"))
+                                                        .view(ChangePassword)
+                                                        .redirect_path(move || {
+                                                            "/login?orig_url=/change-password"
+                                                        })
+                                                        .condition(is_logged_in)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                ProtectedRoute(
+                                                    ProtectedRouteProps::builder()
+                                                        .path(StaticSegment("/sessions"))
+                                                        .view(Sessions)
+                                                        .redirect_path(move || {
+                                                            "/login?orig_url=/sessions"
+                                                        })
+                                                        .condition(is_logged_in)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                ProtectedRoute(
+                                                    ProtectedRouteProps::builder()
+                                                        .path(StaticSegment("/two-factor"))
+                                                        .view(TwoFactor)
+                                                        .redirect_path(move || {
+                                                            "/login?orig_url=/two-factor"
+                                                        })
+                                                        .condition(is_logged_in)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                ProtectedRoute(
+                                                    ProtectedRouteProps::builder()
+                                                        .path((
+                                                            StaticSegment("admin"),
+                                                            StaticSegment("users"),
+                                                            StaticSegment("new"),
+                                                        ))
+                                                        .view(move || {
+                                                            with_role(Role::Admin, AdminCreateUser)
+                                                        })
+                                                        .redirect_path(move || {
+                                                            "/login?orig_url=/admin/users/new"
+                                                        })
+                                                        .condition(is_logged_in)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                ProtectedRoute(
+                                                    ProtectedRouteProps::builder()
+                                                        .path((
+                                                            StaticSegment("admin"),
+                                                            StaticSegment("users"),
+                                                            StaticSegment("edit"),
+                                                        ))
+                                                        .view(move || {
+                                                            with_role(Role::Admin, AdminEditUser)
+                                                        })
+                                                        .redirect_path(move || {
+                                                            "/login?orig_url=/admin/users"
+                                                        })
+                                                        .condition(is_logged_in)
+                                                        .build(),
+                                                )
+                                            },
+                                            {
+                                                ProtectedRoute(
+                                                    ProtectedRouteProps::builder()
+                                                        .path((
+                                                            StaticSegment("admin"),
+                                                            StaticSegment("users"),
+                                                        ))
+                                                        .view(move || {
+                                                            with_role(Role::Admin, AdminUsers)
+                                                        })
+                                                        .redirect_path(move || {
+                                                            "/login?orig_url=/admin/users"
+                                                        })
+                                                        .condition(is_logged_in)
+                                                        .build(),
+                                                )
+                                            },
                                             {
                                                 ProtectedRoute(
                                                     ProtectedRouteProps::builder()
                                                         .path(StaticSegment(""))
                                                         .view(HomePage)
                                                         .redirect_path(move || "/login?orig_url=/")
-                                                        .condition(move || is_logged_in())
+                                                        .condition(is_logged_in)
                                                         .build(),
                                                 )
                                             },
@@ -178,7 +413,7 @@ pub fn App() -> impl IntoView {
                                                                 params.into_iter().last().unwrap();
                                                             format!("/login?orig_url=/{}", orig_url)
                                                         })
-                                                        .condition(move || is_logged_in())
+                                                        .condition(is_logged_in)
                                                         .build(),
                                                 )
                                             },
@@ -188,6 +423,13 @@ pub fn App() -> impl IntoView {
                             ))
                         },
                         { Footer() },
+                        {
+                            SessionExpiryModal(
+                                SessionExpiryModalProps::builder()
+                                    .user_setter(set_user)
+                                    .build(),
+                            )
+                        },
                     )
                 }))
                 .build(),
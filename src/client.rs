@@ -12,14 +12,14 @@ pub struct AuthClient;
 /// to outgoing requests.
 /// # Example
 /// In a leptos server fn:
-/// ```
+/// ```ignore
 ///#[server(client = crate::client::AddAuthHeaderClient)]
 /// pub async fn foo() -> Result<ApiResponse<User>, ServerFnError> {
-///     let req: actix_web::HttpRequest = extract().await?;
-///     // use fields as required
-///     let account_id = req.extensions_mut().get::<Uuid>().unwrap().clone();
-///     let token = req.extensions_mut().get::<String>().unwrap().to_string();
-///     let expires_at = req.extensions_mut().get::<i64>().unwrap().clone();
+///     // see `crate::api::auth_context::AuthContext`
+///     let auth = match AuthContext::extract().await {
+///         Err(err) => return return_early(err),
+///         Ok(auth) => auth,
+///     };
 /// ```
 pub struct AddAuthHeaderClient;
 
@@ -34,12 +34,22 @@ where
 
     fn send(req: Self::Request) -> impl Future<Output = Result<Self::Response, E>> + Send {
         //here all the work gets done
-        let (token, _) = crate::utils::get_login_data_from_session_storage();
-        let headers = req.headers();
-        headers.append(
-            "Authorization",
-            format!("Bearer {}", token.as_str()).as_str(),
-        );
+        // without a token the server will answer with `ApiError::Unauthorized`,
+        // unless the browser sends the session cookie
+        if let Some((token, _)) = crate::utils::get_login_data_from_session_storage()
+            .filter(|(token, _)| !token.is_empty())
+        {
+            let headers = req.headers();
+            headers.append(
+                "Authorization",
+                format!("Bearer {}", token.as_str()).as_str(),
+            );
+        }
+        // server fns of a session need its CSRF token, so every `ActionForm` sends it, too
+        if let Some(csrf_token) = crate::utils::get_csrf_token_from_cookie() {
+            req.headers()
+                .append(crate::utils::CSRF_HEADER, csrf_token.as_str());
+        }
         <BrowserClient as Client<E, IS, OS>>::send(req)
     }
 
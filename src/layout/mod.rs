@@ -1,3 +1,4 @@
 pub mod navbar;
 pub mod footer;
-pub mod server_message;
\ No newline at end of file
+pub mod server_message;
+pub mod session_expiry;
\ No newline at end of file
@@ -1,18 +1,28 @@
 use crate::api::response::ApiResponse;
 use crate::i18n::{t, use_i18n};
 use crate::model::language::Language;
+use crate::model::role::Role;
 use crate::model::user::User;
-use crate::utils::{set_lang_to_i18n, set_lang_to_locale_storage};
+use crate::pages::login::logout;
+use crate::utils::{
+    reload_at, remove_login_data_from_session_storage, set_lang_to_i18n,
+    set_lang_to_locale_storage, set_login_data_to_session_storage,
+};
 use leptos::ev;
 use leptos::html::*;
 use leptos::prelude::*;
 use leptos::reactive::spawn_local;
 use leptos::{component, IntoView};
+use leptos_router::hooks::use_navigate;
+use leptos_router::NavigateOptions;
 use wasm_bindgen::JsCast;
 use web_sys::{Event, HtmlSelectElement};
 
 #[component]
-pub fn NavBar(lang_setter: WriteSignal<String>) -> impl IntoView {
+pub fn NavBar(
+    lang_setter: WriteSignal<String>,
+    user_setter: WriteSignal<Option<User>>,
+) -> impl IntoView {
     let user = use_context::<ReadSignal<Option<User>>>().expect("no user specified in context");
     let lang = use_context::<ReadSignal<String>>().expect("lang missing from context");
     let i18n = use_i18n();
@@ -26,7 +36,13 @@ pub fn NavBar(lang_setter: WriteSignal<String>) -> impl IntoView {
                         .href("/")
                         .child(p().class("fs-3").child("Leptos SSR First"))
                 },
-                { NavBarLoginInfo() },
+                {
+                    NavBarLoginInfo(
+                        NavBarLoginInfoProps::builder()
+                            .user_setter(user_setter)
+                            .build(),
+                    )
+                },
                 {
                     form().class("d-inline-flex p-2").child(
                         select()
@@ -40,21 +56,21 @@ pub fn NavBar(lang_setter: WriteSignal<String>) -> impl IntoView {
                                     .value_of()
                                     .unchecked_into::<HtmlSelectElement>();
                                 let option_value = target.value();
-                                let option_value = if option_value.to_string() == "en" {
-                                    "en"
-                                } else {
-                                    "de"
-                                };
+                                let option_value = if option_value == "en" { "en" } else { "de" };
                                 lang_setter.set(option_value.to_string());
-                                set_lang_to_locale_storage(&option_value);
-                                set_lang_to_i18n(&option_value);
+                                set_lang_to_locale_storage(option_value);
+                                set_lang_to_i18n(option_value);
                                 // set lang to server if applicable
                                 if user.get().is_some() {
                                     let lang: Language = lang.get().into();
                                     spawn_local(async {
-                                        set_lang(lang)
+                                        let response = set_lang(lang)
                                             .await
                                             .expect("Got server error setting lang");
+                                        set_login_data_to_session_storage(
+                                            response.token.as_str(),
+                                            response.expires_at,
+                                        );
                                     });
                                 }
                                 target.blur().expect("Couldn't blur select element");
@@ -81,32 +97,137 @@ pub fn NavBar(lang_setter: WriteSignal<String>) -> impl IntoView {
 }
 
 #[component]
-fn NavBarLoginInfo() -> impl IntoView {
+fn NavBarLoginInfo(user_setter: WriteSignal<Option<User>>) -> impl IntoView {
     let user = use_context::<ReadSignal<Option<User>>>().expect("no user specified in context");
     let i18n = use_i18n();
+    let navigate = use_navigate();
 
-    div().child(span().class("navbar-text opacity-75").child({
-        move || match user.get() {
-            None => t![i18n, notLoggedIn].into_any(),
-            Some(user) => t![i18n, loggedInAs, name = user.name].into_any(),
-        }
-    }))
+    let on_logout = move |_| {
+        let navigate = navigate.clone();
+        spawn_local(async move {
+            // the client state is cleared regardless, e.g., if the session is already expired
+            let _ = logout().await;
+            remove_login_data_from_session_storage();
+            user_setter.set(None);
+            navigate(
+                "/login",
+                NavigateOptions {
+                    resolve: false,
+                    replace: false,
+                    scroll: true,
+                    state: Default::default(),
+                },
+            );
+        });
+    };
+
+    // the admin gets a new session, so the app starts from scratch
+    let on_stop_impersonation = move |_| {
+        spawn_local(async move {
+            if let Ok(response) = stop_impersonation().await {
+                if response.error.is_none() {
+                    set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                    reload_at("/admin/users");
+                }
+            }
+        });
+    };
+
+    div().class("d-inline-flex align-items-center").child((
+        {
+            move || {
+                user.get().and_then(|user| {
+                    user.impersonator.map(|impersonator| {
+                        span()
+                            .class("alert alert-warning py-1 px-2 mb-0 me-3")
+                            .role("status")
+                            .child((
+                                { t![i18n, impersonating, name = user.name, admin = impersonator] },
+                                {
+                                    button()
+                                        .r#type("button")
+                                        .class("btn btn-warning btn-sm ms-2")
+                                        .on(ev::click, on_stop_impersonation)
+                                        .child(t![i18n, stopImpersonating])
+                                },
+                            ))
+                    })
+                })
+            }
+        },
+        {
+            span().class("navbar-text opacity-75").child({
+                move || match user.get() {
+                    None => t![i18n, notLoggedIn].into_any(),
+                    Some(user) => t![i18n, loggedInAs, name = user.name].into_any(),
+                }
+            })
+        },
+        {
+            a().class("link-dark ms-3")
+                .href("/profile")
+                .hidden(move || user.get().is_none())
+                .child(t![i18n, profile])
+        },
+        {
+            a().class("link-dark ms-3")
+                .href("/change-password")
+                .hidden(move || user.get().is_none())
+                .child(t![i18n, changePassword])
+        },
+        {
+            a().class("link-dark ms-3")
+                .href("/sessions")
+                .hidden(move || user.get().is_none())
+                .child(t![i18n, sessions])
+        },
+        {
+            a().class("link-dark ms-3")
+                .href("/two-factor")
+                .hidden(move || user.get().is_none())
+                .child(t![i18n, twoFactor])
+        },
+        {
+            a().class("link-dark ms-3")
+                .href("/admin/users")
+                .hidden(move || {
+                    user.get()
+                        .is_none_or(|user| !user.role.includes(Role::Admin))
+                })
+                .child(t![i18n, userManagement])
+        },
+        {
+            button()
+                .r#type("button")
+                .class("btn btn-outline-secondary btn-sm ms-3")
+                .hidden(move || user.get().is_none())
+                .on(ev::click, on_logout)
+                .child(t![i18n, logout])
+        },
+    ))
 }
 
 #[server(client = crate::client::AddAuthHeaderClient)]
 pub async fn set_lang(lang: Language) -> Result<ApiResponse<User>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use crate::model::role::Role;
     use actix_web::web::Data;
-    use actix_web::HttpMessage;
-    use leptos_actix::extract;
+    use log::{log, Level};
     use sqlx::query;
-    use sqlx::types::Uuid;
     use sqlx::{Pool, Postgres};
 
-    let db_pool = use_context::<Data<Pool<Postgres>>>().expect("No db pool?");
-    let req: actix_web::HttpRequest = extract().await?;
-    let account_id = req.extensions_mut().get::<Uuid>().unwrap().clone();
-    let expires_at = req.extensions_mut().get::<i64>().unwrap().clone();
-    let token = req.extensions_mut().get::<String>().unwrap().clone();
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
 
     //set lang in db
     let account_row = query!(
@@ -114,23 +235,116 @@ pub async fn set_lang(lang: Language) -> Result<ApiResponse<User>, ServerFnError
         UPDATE account
             SET preferred_language = ($1::text)::lang
         WHERE id = $2
-        RETURNING username, preferred_language as "preferred_language: Language"
+        RETURNING username, preferred_language as "preferred_language: Language",
+            role as "role: Role",
+            (SELECT name FROM account impersonator WHERE impersonator.id = $3) as "impersonator?"
         "#,
         lang.to_string(),
-        &account_id
+        &auth.account_id,
+        auth.impersonator_id
     )
     .fetch_one(&**db_pool)
     .await?;
 
+    Ok(auth.response(User {
+        name: account_row.username,
+        preferred_language: account_row.preferred_language.to_string(),
+        role: account_row.role,
+        impersonator: account_row.impersonator,
+    }))
+}
+
+/// Ends the impersonation of the current session and starts a new session for the admin.
+///
+/// The admin only gets the session if the account is still an enabled admin.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn stop_impersonation() -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use crate::pages::login::insert_session;
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+    let impersonator_id = match auth.impersonator_id {
+        None => {
+            log!(
+                Level::Warn,
+                "Stopping impersonation without impersonating: {}",
+                auth.account_id
+            );
+            return return_early(ApiError::Forbidden);
+        }
+        Some(impersonator_id) => impersonator_id,
+    };
+
+    if let Err(err) = session_store.revoke(auth.session_id, auth.account_id).await {
+        return return_early(ApiError::DbError(err.to_string()));
+    }
+    // the impersonation has ended anyway, but the admin may have lost the rights meanwhile
+    let impersonator_row = match query!(
+        "\
+            SELECT role as \"role: Role\", disabled_at \
+            FROM account \
+            WHERE id = $1\
+        ",
+        impersonator_id
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting impersonator: {}",
+                err
+            )))
+        }
+        Ok(row) => row,
+    };
+    if !impersonator_row
+        .is_some_and(|row| row.role.includes(Role::Admin) && row.disabled_at.is_none())
+    {
+        log!(
+            Level::Warn,
+            "Impersonation of account {} stopped, but account {} isn't an enabled admin",
+            auth.account_id,
+            impersonator_id
+        );
+        return return_early(ApiError::Forbidden);
+    }
+    let (token, expires_at) = match insert_session(impersonator_id, None).await {
+        Err(err) => return return_early(err),
+        Ok(session) => session,
+    };
+    log!(
+        Level::Info,
+        "Impersonation of account {} stopped by account {}",
+        auth.account_id,
+        impersonator_id
+    );
+
     Ok(ApiResponse {
+        error: None,
         expires_at,
         token,
-        error: None,
-        data: {
-            User {
-                name: account_row.username,
-                preferred_language: account_row.preferred_language.to_string(),
-            }
-        },
+        data: (),
     })
 }
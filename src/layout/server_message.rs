@@ -63,7 +63,7 @@ impl MessageOfTheDayLevel {
 /// # Derives
 /// - `Serialize`: Enables the struct to be serialized (e.g., to JSON or other formats).
 /// - `Default`: Provides a default implementation for the struct, initializing `message` as an
-///         empty string and `strongish` as an empty vector.
+///   empty string and `strongish` as an empty vector.
 /// - `Clone`: Allows for creating a duplicate of the struct.
 /// - `PartialEq`: Enables comparison of two `MessageOfTheDay` instances for equality.
 ///
@@ -178,7 +178,7 @@ pub fn ServerMessage() -> impl IntoView {
 
 fn show_message(message: &ServerMessageOfTheDay) -> impl IntoView {
     let lang = get_lang();
-    let localized_message = if (move || lang.read() == "de".to_string())() {
+    let localized_message = if lang.read() == "de".to_string() {
         show_localized_message(message.de.clone())
     } else {
         show_localized_message(message.en.clone())
@@ -224,7 +224,7 @@ pub async fn get_message() -> Result<ServerMessageOfTheDay, ServerFnError> {
     let mut file = match file_result {
         Ok(file) => file,
         Err(e) => {
-            log!(Level::Warn, "Couldn't open message file: {}", e.to_string());
+            log!(Level::Warn, "Couldn't open message file: {}", e);
             return Ok(ServerMessageOfTheDay::default());
         }
     };
@@ -235,7 +235,7 @@ pub async fn get_message() -> Result<ServerMessageOfTheDay, ServerFnError> {
             log!(
                 Level::Warn,
                 "Couldn't parse message file: {}",
-                e.to_string()
+                e
             );
             ServerMessageOfTheDay::default()
         });
@@ -0,0 +1,139 @@
+use crate::api::response::ApiResponse;
+use crate::i18n::{t, use_i18n};
+use crate::model::user::User;
+use crate::utils::{
+    get_login_data_from_session_storage, remove_login_data_from_session_storage,
+    set_login_data_to_session_storage,
+};
+use chrono::Utc;
+use leptos::ev;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use leptos::server::OnceResource;
+use leptos::{component, server, IntoView};
+use std::time::Duration;
+
+/// A modal warning the user that the session is about to expire.
+///
+/// The expiry stored in session storage is checked every second. The modal is shown once
+/// less than the configured number of minutes are left and offers to extend the session.
+/// If the session has expired, the user is reset, so protected routes redirect to the login page.
+#[component]
+pub fn SessionExpiryModal(user_setter: WriteSignal<Option<User>>) -> impl IntoView {
+    let user = use_context::<ReadSignal<Option<User>>>().expect("no user specified in context");
+    let i18n = use_i18n();
+    let warning_mins_resource = OnceResource::new(get_session_expiry_warning_mins());
+    // the seconds left until the session expires, `None` if there is no session
+    let (remaining_secs, set_remaining_secs) = signal(None::<i64>);
+
+    Effect::new(move || {
+        let handle = set_interval_with_handle(
+            move || {
+                if user.get_untracked().is_none() {
+                    set_remaining_secs.set(None);
+                    return;
+                }
+                match get_login_data_from_session_storage() {
+                    Some((_, expires)) => {
+                        let remaining = expires - Utc::now().timestamp();
+                        if remaining > 0 {
+                            set_remaining_secs.set(Some(remaining));
+                        } else {
+                            remove_login_data_from_session_storage();
+                            set_remaining_secs.set(None);
+                            user_setter.set(None);
+                        }
+                    }
+                    None => set_remaining_secs.set(None),
+                }
+            },
+            Duration::from_secs(1),
+        )
+        .expect("Couldn't set interval for session expiry");
+        on_cleanup(move || handle.clear());
+    });
+
+    let show_warning = move || match (remaining_secs.get(), warning_mins_resource.get()) {
+        (Some(remaining), Some(Ok(warning_mins))) => remaining <= warning_mins as i64 * 60,
+        _ => false,
+    };
+    let remaining_text = move || {
+        let remaining = remaining_secs.get().unwrap_or_default();
+        format!("{}:{:02}", remaining / 60, remaining % 60)
+    };
+
+    let on_extend = move |_| {
+        spawn_local(async move {
+            match extend_session().await {
+                Ok(response) if response.error.is_none() => {
+                    set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                }
+                // the session is gone on the server, e.g., it was revoked
+                _ => {
+                    remove_login_data_from_session_storage();
+                    set_remaining_secs.set(None);
+                    user_setter.set(None);
+                }
+            }
+        });
+    };
+
+    div().hidden(move || !show_warning()).child((
+        {
+            div()
+                .class("modal d-block")
+                .role("dialog")
+                .attr("tabindex", "-1")
+                .attr("aria-modal", "true")
+                .child(div().class("modal-dialog modal-dialog-centered").child(
+                    div().class("modal-content").child((
+                        {
+                            div().class("modal-header").child(
+                                h5().class("modal-title")
+                                    .child(t![i18n, sessionExpiryTitle]),
+                            )
+                        },
+                        {
+                            div().class("modal-body").child(p().child(move || {
+                                t![i18n, sessionExpiryWarning, remaining = remaining_text()]
+                            }))
+                        },
+                        {
+                            div().class("modal-footer").child(
+                                button()
+                                    .r#type("button")
+                                    .class("btn btn-primary")
+                                    .on(ev::click, on_extend)
+                                    .child(t![i18n, extendSession]),
+                            )
+                        },
+                    )),
+                ))
+        },
+        { div().class("modal-backdrop show") },
+    ))
+}
+
+#[server]
+pub async fn get_session_expiry_warning_mins() -> Result<u8, ServerFnError> {
+    use crate::server_utils::configuration::SessionExpiryWarningSettings;
+    use actix_web::web::Data;
+
+    let warning = use_context::<Data<SessionExpiryWarningSettings>>()
+        .ok_or_else(|| ServerFnError::new("No session expiry warning found in context"))?;
+
+    Ok(warning.mins)
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn extend_session() -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+
+    // the session has already been touched by the `Authorisation` middleware
+    match AuthContext::extract().await {
+        Err(err) => return_early(err),
+        Ok(auth) => Ok(auth.response(())),
+    }
+}
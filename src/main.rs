@@ -1,6 +1,8 @@
 #[cfg(feature = "ssr")]
 use actix_web::web::Data;
+#[cfg(feature = "ssr")]
 use leptos_ssr_first::api;
+#[cfg(feature = "ssr")]
 use leptos_ssr_first::server_utils::background_task;
 
 #[cfg(feature = "ssr")]
@@ -13,9 +15,16 @@ async fn main() -> std::io::Result<()> {
     use leptos_actix::handle_server_fns_with_context;
     use leptos_actix::{generate_route_list, LeptosRoutes};
     use leptos_ssr_first::app::*;
-    use leptos_ssr_first::server_utils::authorization::Authorisation;
+    use leptos_ssr_first::server_utils::auth_backend;
+    use leptos_ssr_first::server_utils::authorization::{Authorisation, PageAuthorisation};
     use leptos_ssr_first::server_utils::configuration;
+    use leptos_ssr_first::server_utils::configuration::SessionExpiryWarningSettings;
+    use leptos_ssr_first::server_utils::csrf::Csrf;
     use leptos_ssr_first::server_utils::logging::Logger;
+    use leptos_ssr_first::server_utils::mailer;
+    use leptos_ssr_first::server_utils::public_endpoints::public_endpoints;
+    use leptos_ssr_first::server_utils::rate_limit::RateLimit;
+    use leptos_ssr_first::server_utils::session_store;
     use sqlx::{Pool, Postgres};
 
     //LEPTOS CODE
@@ -30,17 +39,44 @@ async fn main() -> std::io::Result<()> {
     let addr_clone = addr.clone();
     let configuration_clone = configuration.clone();
     Logger::init(configuration.log).await.expect("Couldn't initialize logger");
-    let jwt_keys = api::jwt::get_jwt_keys(configuration.server.session_secret);
+    let jwt_keys =
+        api::jwt::get_jwt_keys(&configuration.server.jwt).expect("Couldn't set up JWT keys.");
     let dummy_hash = configuration.server.dummy_bcrypt_hash;
+    let expiry_warning = SessionExpiryWarningSettings {
+        mins: configuration.server.session_expiry_warning_mins,
+    };
+    let login_lockout = configuration.server.login_lockout.clone();
+    let password_reset = configuration.server.password_reset.clone();
+    let totp = configuration.server.totp.clone();
+    let oidc = configuration.server.oidc.clone();
+    let csrf = configuration.server.csrf.clone();
+    let public_endpoints = public_endpoints();
+    public_endpoints.log();
+    let mailer = mailer::new_mailer(&configuration.mail).expect("Couldn't set up mailer.");
+    let auth_backend = auth_backend::new_auth_backend(&configuration.server.auth_backends)
+        .expect("Couldn't set up auth backends.");
+    // created once, so all workers share the same buckets
+    let rate_limit = RateLimit::new(configuration.server.rate_limits.clone());
+    let ip_rate_limit = RateLimit::by_ip(configuration.server.ip_rate_limits.clone());
     let db_url = configuration.database.connection_string();
     let db_pool = Pool::<Postgres>::connect(db_url.as_str())
         .await
         .expect("Couldn't connect to database.");
-    let _scheduler =
-        match background_task::setup_scheduler(db_pool.clone(), configuration_clone).await {
-            Ok(scheduler) => scheduler,
-            Err(e) => panic!("Failed to setup scheduler: {}", e),
-        };
+    let session_store = session_store::new_session_store(
+        &configuration.server.session_store,
+        &db_pool,
+        configuration.server.session_expiry_mins,
+    );
+    let _scheduler = match background_task::setup_scheduler(
+        db_pool.clone(),
+        session_store.clone(),
+        configuration_clone,
+    )
+    .await
+    {
+        Ok(scheduler) => scheduler,
+        Err(e) => panic!("Failed to setup scheduler: {}", e),
+    };
     //LSF CODE END
 
     HttpServer::new(move || {
@@ -55,6 +91,25 @@ async fn main() -> std::io::Result<()> {
         let db_pool_clone_1 = db_pool.clone();
         let jwt_keys_clone_1 = jwt_keys.clone();
         let dummy_hash_clone_1 = dummy_hash.clone();
+        let login_lockout_clone = login_lockout.clone();
+        let login_lockout_clone_1 = login_lockout.clone();
+        let password_reset_clone = password_reset.clone();
+        let password_reset_clone_1 = password_reset.clone();
+        let totp_clone = totp.clone();
+        let totp_clone_1 = totp.clone();
+        let oidc_clone = oidc.clone();
+        let oidc_clone_1 = oidc.clone();
+        let csrf_clone = csrf.clone();
+        let csrf_clone_1 = csrf.clone();
+        let mailer_clone = mailer.clone();
+        let mailer_clone_1 = mailer.clone();
+        let auth_backend_clone = auth_backend.clone();
+        let auth_backend_clone_1 = auth_backend.clone();
+        let session_store_clone = session_store.clone();
+        let session_store_clone_1 = session_store.clone();
+        let jwt_keys_clone_2 = jwt_keys.clone();
+        let db_pool_clone_2 = db_pool.clone();
+        let session_store_clone_2 = session_store.clone();
         //LSF CODE END
 
         println!("listening on {}", addr);
@@ -66,7 +121,14 @@ async fn main() -> std::io::Result<()> {
                     .app_data(Data::new(db_pool_clone.clone()))
                     .app_data(Data::new(jwt_keys_clone.clone()))
                     .app_data(Data::new(dummy_hash_clone.clone()))
-                    .wrap(Authorisation)
+                    .app_data(Data::from(session_store_clone.clone()))
+                    // runs after `Authorisation` to know the session of a request
+                    .wrap(Csrf::new(csrf.allowed_origin.clone(), public_endpoints.clone()))
+                    // runs after `Authorisation` to know the account of a request
+                    .wrap(rate_limit.clone())
+                    .wrap(Authorisation::new(public_endpoints.clone()))
+                    // runs before `Authorisation`, so floods don't reach the database
+                    .wrap(ip_rate_limit.clone())
                     .route(
                         "/{func_name:.*}",
                         handle_server_fns_with_context(move || {
@@ -74,6 +136,15 @@ async fn main() -> std::io::Result<()> {
                             provide_context(Data::new(db_pool_clone.clone()));
                             provide_context(Data::new(jwt_keys_clone.clone()));
                             provide_context(Data::new(dummy_hash_clone.clone()));
+                            provide_context(Data::new(expiry_warning));
+                            provide_context(Data::new(login_lockout_clone.clone()));
+                            provide_context(Data::new(password_reset_clone.clone()));
+                            provide_context(Data::new(totp_clone.clone()));
+                            provide_context(Data::new(oidc_clone.clone()));
+                            provide_context(Data::new(csrf_clone.clone()));
+                            provide_context(Data::from(mailer_clone.clone()));
+                            provide_context(Data::from(auth_backend_clone.clone()));
+                            provide_context(Data::from(session_store_clone.clone()));
                         }),
                     ),
             )
@@ -84,10 +155,27 @@ async fn main() -> std::io::Result<()> {
             .service(Files::new("/assets", &site_root))
             // serve the favicon from /favicon.ico
             .service(favicon)
+            //LSF CODE
+            .service(jwks)
+            .app_data(Data::new(jwt_keys_clone_2))
+            .app_data(Data::new(db_pool_clone_2))
+            .app_data(Data::from(session_store_clone_2))
+            // resolves the session of the session cookie for rendering protected pages
+            .wrap(PageAuthorisation)
+            //LSF CODE END
             .leptos_routes_with_context(routes, {move || {
                 provide_context(Data::new(db_pool_clone_1.clone()));
                 provide_context(Data::new(jwt_keys_clone_1.clone()));
                 provide_context(Data::new(dummy_hash_clone_1.clone()));
+                provide_context(Data::new(expiry_warning));
+                provide_context(Data::new(login_lockout_clone_1.clone()));
+                provide_context(Data::new(password_reset_clone_1.clone()));
+                provide_context(Data::new(totp_clone_1.clone()));
+                provide_context(Data::new(oidc_clone_1.clone()));
+                provide_context(Data::new(csrf_clone_1.clone()));
+                provide_context(Data::from(mailer_clone_1.clone()));
+                provide_context(Data::from(auth_backend_clone_1.clone()));
+                provide_context(Data::from(session_store_clone_1.clone()));
             }} ,{
                 let leptos_options = leptos_options.clone();
                 move || shell(leptos_options.clone())
@@ -112,6 +200,15 @@ async fn favicon(
     ))?)
 }
 
+/// The public keys of the session tokens, so other services can verify them.
+///
+/// It's outside of `/api`, so it needs no authorization. With HMAC keys only, it's empty.
+#[cfg(feature = "ssr")]
+#[actix_web::get("/.well-known/jwks.json")]
+async fn jwks(jwt_keys: Data<api::jwt::JwtKeys>) -> actix_web::HttpResponse {
+    actix_web::HttpResponse::Ok().json(jwt_keys.jwks())
+}
+
 #[cfg(not(any(feature = "ssr", feature = "csr")))]
 pub fn main() {
     // no client-side main function
@@ -0,0 +1,15 @@
+use crate::model::language::Language;
+use crate::model::role::Role;
+use serde::{Deserialize, Serialize};
+
+/// An account as managed by admins on the user pages.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Account {
+    pub(crate) id: String,
+    pub(crate) username: String,
+    pub(crate) name: String,
+    pub(crate) email: Option<String>,
+    pub(crate) preferred_language: Language,
+    pub(crate) role: Role,
+    pub(crate) disabled: bool,
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// A personal API token of the logged-in user as listed on the profile page.
+///
+/// The token itself is only shown once after creating it. All points in time are UTC timestamps
+/// in seconds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiToken {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) created_at: i64,
+    pub(crate) expires_at: Option<i64>,
+    pub(crate) last_used_at: Option<i64>,
+}
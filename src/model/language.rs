@@ -20,9 +20,9 @@ impl Display for Language {
     }
 }
 
-impl Into<&str> for Language {
-    fn into(self) -> &'static str {
-        match self {
+impl From<Language> for &str {
+    fn from(val: Language) -> Self {
+        match val {
             Language::En => "en",
             Language::De => "de",
         }
@@ -1,2 +1,8 @@
+pub mod account;
+pub mod api_token;
 pub mod user;
-pub mod language;
\ No newline at end of file
+pub mod language;
+pub mod profile;
+pub mod role;
+pub mod session;
+pub mod two_factor;
\ No newline at end of file
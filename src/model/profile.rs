@@ -0,0 +1,15 @@
+use crate::model::language::Language;
+use serde::{Deserialize, Serialize};
+
+/// The account of the logged-in user as shown on the profile page.
+///
+/// All points in time are UTC timestamps in seconds.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    pub(crate) username: String,
+    pub(crate) name: String,
+    pub(crate) preferred_language: Language,
+    pub(crate) created_at: i64,
+    /// The login before the current one, none for the first login.
+    pub(crate) previous_login_at: Option<i64>,
+}
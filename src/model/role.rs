@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// The role of an account, deciding which pages and server fns it may use.
+///
+/// Roles are ordered by their privileges, every role includes the ones before it.
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(type_name = "role", rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    /// Whether an account with this role may do what needs the `required` one.
+    pub fn includes(&self, required: Role) -> bool {
+        *self >= required
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match &self {
+            Role::User => "user".to_string(),
+            Role::Admin => "admin".to_string(),
+        };
+        write!(f, "{}", str)
+    }
+}
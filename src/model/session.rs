@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// A session of the logged-in user as shown on the sessions page.
+///
+/// All points in time are UTC timestamps in seconds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Session {
+    pub(crate) id: String,
+    pub(crate) created_at: i64,
+    pub(crate) last_used_at: i64,
+    pub(crate) expires_at: i64,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) is_current: bool,
+}
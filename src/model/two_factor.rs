@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+/// Returned by `login` instead of a session if the account has 2FA enabled.
+///
+/// The `challenge` has to be sent to `verify_totp` together with a code
+/// before the UTC timestamp `expires_at`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TotpChallenge {
+    pub(crate) challenge: String,
+    pub(crate) expires_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TotpStatus {
+    pub(crate) enabled: bool,
+    pub(crate) recovery_codes_left: i64,
+}
+
+/// What the user needs to add the account to an authenticator app.
+///
+/// `qr_code` is a base64 encoded PNG of the `otpauth_uri`, `secret` is for manual entry.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TotpEnrollment {
+    pub(crate) otpauth_uri: String,
+    pub(crate) qr_code: String,
+    pub(crate) secret: String,
+}
@@ -1,7 +1,11 @@
+use crate::model::role::Role;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct User {
     pub(crate) name: String,
     pub(crate) preferred_language: String,
+    pub(crate) role: Role,
+    /// The name of the admin impersonating the user in the current session.
+    pub(crate) impersonator: Option<String>,
 }
@@ -0,0 +1,1415 @@
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::model::account::Account;
+use crate::model::language::Language;
+use crate::model::role::Role;
+use crate::pages::login::{
+    length_validated, show_error, NAME_MAX_LENGTH, PASSWORD_MAX_LENGTH, USERNAME_MAX_LENGTH,
+};
+use crate::utils::{csrf_field, reload_at, set_login_data_to_session_storage};
+use leptos::ev;
+use leptos::form::ActionForm;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use leptos::tachys::html::event;
+use leptos::{component, server, IntoView};
+use leptos_router::hooks::{use_navigate, use_query_map};
+use leptos_router::NavigateOptions;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlFormElement, SubmitEvent};
+
+/// The length of `account.email`, the maximum length of a mail address.
+const EMAIL_MAX_LENGTH: u8 = 254;
+/// More accounts aren't listed, the search has to be narrowed instead.
+#[cfg(feature = "ssr")]
+const LIST_LIMIT: i64 = 100;
+
+/// The fields of an account an admin can set, except for the password.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountCallParams {
+    username: String,
+    name: String,
+    email: String,
+    preferred_language: Language,
+    role: Role,
+}
+
+enum AccountCallParamsError {
+    Username,
+    Name,
+    Email,
+}
+
+impl AccountCallParams {
+    fn validated(&self) -> Result<AccountCallParams, AccountCallParamsError> {
+        if !length_validated(&self.username, USERNAME_MAX_LENGTH) {
+            return Err(AccountCallParamsError::Username);
+        };
+        if !length_validated(&self.name, NAME_MAX_LENGTH) {
+            return Err(AccountCallParamsError::Name);
+        };
+        if !self.email.is_empty()
+            && (!length_validated(&self.email, EMAIL_MAX_LENGTH) || !self.email.contains('@'))
+        {
+            return Err(AccountCallParamsError::Email);
+        };
+
+        Ok(self.clone())
+    }
+
+    /// The mail address is optional, an empty one isn't stored.
+    #[cfg(feature = "ssr")]
+    fn email(&self) -> Option<String> {
+        if self.email.is_empty() {
+            None
+        } else {
+            Some(self.email.clone())
+        }
+    }
+}
+
+/// Shows the index of the form's input div for a validation error.
+fn show_account_error(
+    ev: &SubmitEvent,
+    form: &HtmlFormElement,
+    params: &AccountCallParams,
+) -> Result<(), ()> {
+    match params.validated() {
+        Ok(_) => return Ok(()),
+        Err(AccountCallParamsError::Username) => show_error(ev, form, 0),
+        Err(AccountCallParamsError::Name) => show_error(ev, form, 1),
+        Err(AccountCallParamsError::Email) => show_error(ev, form, 2),
+    }
+    Err(())
+}
+
+#[component]
+pub fn AdminUsers() -> impl IntoView {
+    let i18n = use_i18n();
+    let (search, set_search) = signal(String::new());
+    let accounts = LocalResource::new(move || list_accounts(search.get()));
+    let (action_error, set_action_error) = signal(None::<String>);
+
+    // keep the stored expiry in sync, as loading the accounts extends the current session
+    Effect::new(move || {
+        if let Some(Ok(response)) = accounts.get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+
+    let handle_result = move |result: Result<ApiResponse<()>, ServerFnError>| {
+        match result {
+            Ok(response) => match response.error {
+                None => {
+                    set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                    set_action_error.set(None);
+                }
+                Some(err) => set_action_error.set(Some(err.to_string())),
+            },
+            Err(err) => set_action_error.set(Some(err.to_string())),
+        }
+        accounts.refetch();
+    };
+    let set_disabled = move |id: String, disabled: bool| {
+        spawn_local(async move {
+            handle_result(set_account_disabled(id, disabled).await);
+        });
+    };
+    let delete = move |id: String, username: String| {
+        let confirmed = web_sys::window()
+            .expect("no global `window` exists")
+            .confirm_with_message(&t_string!(i18n, confirmDeleteAccount, username = username))
+            .unwrap_or(false);
+        if confirmed {
+            spawn_local(async move {
+                handle_result(delete_account(id).await);
+            });
+        }
+    };
+
+    let impersonate = move |id: String| {
+        spawn_local(async move {
+            match impersonate_account(id).await {
+                Ok(response) if response.error.is_none() => {
+                    set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                    reload_at("/");
+                }
+                result => handle_result(result),
+            }
+        });
+    };
+
+    let account_list = move || match accounts.get() {
+        None => div()
+            .class("text-center")
+            .child(
+                div()
+                    .class("spinner-border")
+                    .role("status")
+                    .child(span().class("visually-hidden").child(t!(i18n, loading))),
+            )
+            .into_any(),
+        Some(Ok(response)) => match response.error {
+            None => account_table(response.data, set_disabled, delete, impersonate).into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+    };
+    let action_message = move || match action_error.get() {
+        None => div().hidden(true).into_any(),
+        Some(err) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err))
+            .into_any(),
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, userManagement]) },
+        {
+            div().class("d-flex gap-2 mb-3").child((
+                {
+                    input()
+                        .r#type("search")
+                        .class("form-control")
+                        .aria_label(move || t_string!(i18n, searchAccounts))
+                        .placeholder(move || t_string!(i18n, searchAccounts))
+                        .on(ev::input, move |ev| set_search.set(event_target_value(&ev)))
+                },
+                {
+                    a().class("btn btn-primary text-nowrap")
+                        .href("/admin/users/new")
+                        .child(t![i18n, createAccount])
+                },
+            ))
+        },
+        { div().child(action_message) },
+        { div().child(account_list) },
+    ))
+}
+
+fn account_table(
+    accounts: Vec<Account>,
+    set_disabled: impl Fn(String, bool) + Copy + 'static,
+    delete: impl Fn(String, String) + Copy + 'static,
+    impersonate: impl Fn(String) + Copy + 'static,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let rows = accounts
+        .into_iter()
+        .map(|account| {
+            let id = account.id.clone();
+            let id_1 = account.id.clone();
+            let id_2 = account.id.clone();
+            let username = account.username.clone();
+            tr().child((
+                { td().child(account.username.clone()) },
+                { td().child(account.name) },
+                { td().child(account.email.unwrap_or_default()) },
+                {
+                    td().child(match account.role {
+                        Role::Admin => t![i18n, roleAdmin].into_any(),
+                        Role::User => t![i18n, roleUser].into_any(),
+                    })
+                },
+                {
+                    td().child(if account.disabled {
+                        span()
+                            .class("badge text-bg-secondary")
+                            .child(t![i18n, accountStatusDisabled])
+                            .into_any()
+                    } else {
+                        span()
+                            .class("badge text-bg-success")
+                            .child(t![i18n, accountStatusActive])
+                            .into_any()
+                    })
+                },
+                {
+                    td().class("text-nowrap").child((
+                        {
+                            a().class("btn btn-outline-primary btn-sm me-1")
+                                .href(format!("/admin/users/edit?id={}", account.id))
+                                .child(t![i18n, editAccount])
+                        },
+                        {
+                            button()
+                                .r#type("button")
+                                .class("btn btn-outline-secondary btn-sm me-1")
+                                .on(ev::click, move |_| {
+                                    set_disabled(id.clone(), !account.disabled)
+                                })
+                                .child(if account.disabled {
+                                    t![i18n, enableAccount].into_any()
+                                } else {
+                                    t![i18n, disableAccount].into_any()
+                                })
+                        },
+                        {
+                            button()
+                                .r#type("button")
+                                .class("btn btn-outline-secondary btn-sm me-1")
+                                // admins and disabled accounts can't be impersonated
+                                .hidden(account.role.includes(Role::Admin) || account.disabled)
+                                .on(ev::click, move |_| impersonate(id_2.clone()))
+                                .child(t![i18n, impersonateAccount])
+                        },
+                        {
+                            button()
+                                .r#type("button")
+                                .class("btn btn-outline-danger btn-sm")
+                                .on(ev::click, move |_| delete(id_1.clone(), username.clone()))
+                                .child(t![i18n, deleteAccount])
+                        },
+                    ))
+                },
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    table().class("table").child((
+        {
+            thead().child(tr().child((
+                { th().attr("scope", "col").child(t![i18n, username]) },
+                { th().attr("scope", "col").child(t![i18n, name]) },
+                { th().attr("scope", "col").child(t![i18n, email]) },
+                { th().attr("scope", "col").child(t![i18n, role]) },
+                { th().attr("scope", "col").child(t![i18n, accountStatus]) },
+                { th().attr("scope", "col") },
+            )))
+        },
+        { tbody().child(rows) },
+    ))
+}
+
+/// The inputs shared by the create and edit forms, prefilled with `account`.
+///
+/// Validation errors are shown by their index, see `show_account_error`.
+fn account_fields(account: &Account) -> impl IntoView {
+    let i18n = use_i18n();
+    let text_input =
+        |input_type: &'static str, id: &'static str, name: &'static str, value: String| {
+            input()
+                .r#type(input_type)
+                .class("form-control")
+                .id(id)
+                .name(name)
+                .value(value)
+        };
+
+    (
+        div().class("mb-3 col-xs-1 col-xl-2").child((
+            {
+                label()
+                    .class("form-label")
+                    .r#for("ref1")
+                    .child(t![i18n, username])
+            },
+            {
+                text_input("text", "ref1", "params[username]", account.username.clone())
+                    .required(true)
+            },
+            {
+                div()
+                    .class("invalid-feedback")
+                    .child(t!(i18n, usernameRequired))
+            },
+        )),
+        div().class("mb-3 col-xs-1 col-xl-2").child((
+            {
+                label()
+                    .class("form-label")
+                    .r#for("ref2")
+                    .child(t![i18n, name])
+            },
+            { text_input("text", "ref2", "params[name]", account.name.clone()).required(true) },
+            {
+                div()
+                    .class("invalid-feedback")
+                    .child(t!(i18n, nameRequired))
+            },
+        )),
+        div().class("mb-3 col-xs-1 col-xl-2").child((
+            {
+                label()
+                    .class("form-label")
+                    .r#for("ref3")
+                    .child(t![i18n, email])
+            },
+            {
+                text_input(
+                    "email",
+                    "ref3",
+                    "params[email]",
+                    account.email.clone().unwrap_or_default(),
+                )
+            },
+            {
+                div()
+                    .class("invalid-feedback")
+                    .child(t!(i18n, emailInvalid))
+            },
+        )),
+        // the option values are the serialized enum variants
+        div().class("mb-3 col-xs-1 col-xl-2").child((
+            {
+                label()
+                    .class("form-label")
+                    .r#for("ref4")
+                    .child(t![i18n, preferred])
+            },
+            {
+                select()
+                    .class("form-select")
+                    .id("ref4")
+                    .name("params[preferred_language]")
+                    .child((
+                        {
+                            option()
+                                .value("En")
+                                .selected(account.preferred_language == Language::En)
+                                .child(t![i18n, english])
+                        },
+                        {
+                            option()
+                                .value("De")
+                                .selected(account.preferred_language == Language::De)
+                                .child(t![i18n, german])
+                        },
+                    ))
+            },
+        )),
+        div().class("mb-3 col-xs-1 col-xl-2").child((
+            {
+                label()
+                    .class("form-label")
+                    .r#for("ref5")
+                    .child(t![i18n, role])
+            },
+            {
+                select()
+                    .class("form-select")
+                    .id("ref5")
+                    .name("params[role]")
+                    .child((
+                        {
+                            option()
+                                .value("User")
+                                .selected(account.role == Role::User)
+                                .child(t![i18n, roleUser])
+                        },
+                        {
+                            option()
+                                .value("Admin")
+                                .selected(account.role == Role::Admin)
+                                .child(t![i18n, roleAdmin])
+                        },
+                    ))
+            },
+        )),
+    )
+}
+
+/// The message below the create and edit forms.
+fn save_message(value: Option<Result<ApiResponse<()>, ServerFnError>>) -> AnyView {
+    let i18n = use_i18n();
+
+    match value {
+        Some(Ok(response)) => match response.error {
+            None => div().hidden(true).into_any(),
+            Some(ApiError::UsernameTaken) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, usernameTaken))
+                .into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+        None => div().hidden(true).into_any(),
+    }
+}
+
+/// Goes back to the account list after a successful save.
+fn back_to_list_on_success(value: Option<Result<ApiResponse<()>, ServerFnError>>) {
+    if let Some(Ok(response)) = value {
+        if response.error.is_none() {
+            set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            let navigate = use_navigate();
+            navigate("/admin/users", NavigateOptions::default());
+        }
+    }
+}
+
+#[component]
+pub fn AdminCreateUser() -> impl IntoView {
+    let i18n = use_i18n();
+    let create_account = ServerAction::<CreateAccount>::new();
+
+    Effect::new(move || back_to_list_on_success(create_account.value().get()));
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match CreateAccount::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+        if show_account_error(&ev, &form, &data.params).is_ok()
+            && !length_validated(&data.password, PASSWORD_MAX_LENGTH)
+        {
+            show_error(&ev, &form, 5);
+        }
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, createAccount]) },
+        {
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(create_account)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            account_fields(&Account::default()),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref6")
+                                        .child(t![i18n, password])
+                                },
+                                {
+                                    input()
+                                        .r#type("password")
+                                        .class("form-control")
+                                        .id("ref6")
+                                        .name("password")
+                                        .required(true)
+                                        .attr("autocomplete", "new-password")
+                                },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, passwordRequired))
+                                },
+                            )),
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-primary")
+                                    .child(t![i18n, createAccount])
+                            },
+                            {
+                                div()
+                                    .class("mt-2")
+                                    .child(move || save_message(create_account.value().get()))
+                            },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            ))
+        },
+        {
+            a().href("/admin/users")
+                .child(t![i18n, backToUserManagement])
+        },
+    ))
+}
+
+#[component]
+pub fn AdminEditUser() -> impl IntoView {
+    let i18n = use_i18n();
+    let id = use_query_map()
+        .get_untracked()
+        .get("id")
+        .unwrap_or_default();
+    let id_clone = id.clone();
+    let account = LocalResource::new(move || get_account(id_clone.clone()));
+    let update_account = ServerAction::<UpdateAccount>::new();
+    let reset_account_password = ServerAction::<ResetAccountPassword>::new();
+
+    Effect::new(move || {
+        if let Some(Ok(response)) = account.get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+    Effect::new(move || back_to_list_on_success(update_account.value().get()));
+    Effect::new(move || {
+        if let Some(Ok(response)) = reset_account_password.value().get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match UpdateAccount::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+        let _ = show_account_error(&ev, &form, &data.params);
+    };
+    let password_validated_on_client = move |ev: SubmitEvent| {
+        let data = match ResetAccountPassword::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        if !length_validated(&data.new_password, PASSWORD_MAX_LENGTH) {
+            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+            show_error(&ev, &form, 0);
+        }
+    };
+
+    let reset_message = move || match reset_account_password.value().get() {
+        Some(Ok(response)) => match response.error {
+            None => div()
+                .class("alert alert-success")
+                .child(t!(i18n, accountPasswordReset))
+                .into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+        None => div().hidden(true).into_any(),
+    };
+
+    let forms = move |account: Account| {
+        let id = account.id.clone();
+        let id_1 = account.id.clone();
+        (
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(update_account)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            account_fields(&account),
+                            { input().r#type("hidden").name("id").value(id.clone()) },
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-primary")
+                                    .child(t![i18n, saveAccount])
+                            },
+                            {
+                                div()
+                                    .class("mt-2")
+                                    .child(move || save_message(update_account.value().get()))
+                            },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            )),
+            h2().class("mt-4").child(t![i18n, resetPassword]),
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(reset_account_password)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref7")
+                                        .child(t![i18n, newPassword])
+                                },
+                                {
+                                    input()
+                                        .r#type("password")
+                                        .class("form-control")
+                                        .id("ref7")
+                                        .name("new_password")
+                                        .required(true)
+                                        .attr("autocomplete", "new-password")
+                                },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, passwordRequired))
+                                },
+                            )),
+                            { input().r#type("hidden").name("id").value(id_1.clone()) },
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-outline-danger")
+                                    .child(t![i18n, resetPassword])
+                            },
+                            { div().class("mt-2").child(reset_message) },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                password_validated_on_client,
+            )),
+        )
+    };
+
+    let content = move || match account.get() {
+        None => div()
+            .class("text-center")
+            .child(
+                div()
+                    .class("spinner-border")
+                    .role("status")
+                    .child(span().class("visually-hidden").child(t!(i18n, loading))),
+            )
+            .into_any(),
+        Some(Ok(response)) => match response.error {
+            None => div().child(forms(response.data)).into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, editAccount]) },
+        { div().child(content) },
+        {
+            a().href("/admin/users")
+                .child(t![i18n, backToUserManagement])
+        },
+    ))
+}
+
+/// Parses the id of an account sent by the user pages.
+#[cfg(feature = "ssr")]
+fn parse_account_id(id: &str) -> Result<sqlx::types::Uuid, ApiError> {
+    use std::str::FromStr;
+
+    sqlx::types::Uuid::from_str(id)
+        .map_err(|_| ApiError::UnexpectedError("Invalid account id".to_string()))
+}
+
+/// Lists the accounts whose username or name contains `search`, ignoring the case.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn list_accounts(search: String) -> Result<ApiResponse<Vec<Account>>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+
+    // wildcards typed by the admin are searched for literally
+    let pattern = format!(
+        "%{}%",
+        search
+            .trim()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    let account_rows = match query!(
+        "\
+            SELECT id, username, name, email, \
+                preferred_language as \"preferred_language: Language\", role as \"role: Role\", \
+                disabled_at \
+            FROM account \
+            WHERE username ILIKE $1 OR name ILIKE $1 \
+            ORDER BY username \
+            LIMIT $2\
+        ",
+        pattern,
+        LIST_LIMIT
+    )
+    .fetch_all(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting accounts: {}",
+                err
+            )))
+        }
+        Ok(rows) => rows,
+    };
+
+    Ok(auth.response(
+        account_rows
+            .into_iter()
+            .map(|row| Account {
+                id: row.id.to_string(),
+                username: row.username,
+                name: row.name,
+                email: row.email,
+                preferred_language: row.preferred_language,
+                role: row.role,
+                disabled: row.disabled_at.is_some(),
+            })
+            .collect(),
+    ))
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn get_account(id: String) -> Result<ApiResponse<Account>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+    let account_id = match parse_account_id(&id) {
+        Err(err) => return return_early(err),
+        Ok(account_id) => account_id,
+    };
+
+    let account_row = match query!(
+        "\
+            SELECT id, username, name, email, \
+                preferred_language as \"preferred_language: Language\", role as \"role: Role\", \
+                disabled_at \
+            FROM account \
+            WHERE id = $1\
+        ",
+        account_id
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting account: {}",
+                err
+            )))
+        }
+        Ok(None) => return return_early(ApiError::UnexpectedError("Unknown account".to_string())),
+        Ok(Some(row)) => row,
+    };
+
+    Ok(auth.response(Account {
+        id: account_row.id.to_string(),
+        username: account_row.username,
+        name: account_row.name,
+        email: account_row.email,
+        preferred_language: account_row.preferred_language,
+        role: account_row.role,
+        disabled: account_row.disabled_at.is_some(),
+    }))
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn create_account(
+    params: AccountCallParams,
+    password: String,
+) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use actix_web::web::Data;
+    use bcrypt::{hash, DEFAULT_COST};
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+    let params = match params.validated() {
+        Err(_) => {
+            log!(Level::Warn, "Invalid account params");
+            return return_early(ApiError::UnexpectedError(
+                "Invalid account params".to_string(),
+            ));
+        }
+        Ok(params) => params,
+    };
+    if !length_validated(&password, PASSWORD_MAX_LENGTH) {
+        return return_early(ApiError::UnexpectedError(
+            "Invalid password params".to_string(),
+        ));
+    }
+    let pw_hash = match hash(&password, DEFAULT_COST) {
+        Err(err) => {
+            log!(Level::Error, "Error hashing password: {}", err);
+            return return_early(ApiError::UnexpectedError("Hashing Error".to_string()));
+        }
+        Ok(pw_hash) => pw_hash,
+    };
+
+    let insert_result = query!(
+        "\
+            INSERT INTO account (username, pw_hash, name, email, preferred_language, role) \
+            VALUES ($1, $2, $3, $4, $5, $6) \
+            RETURNING id\
+        ",
+        params.username,
+        pw_hash,
+        params.name,
+        params.email(),
+        params.preferred_language.clone() as Language,
+        params.role as Role
+    )
+    .fetch_one(&**db_pool)
+    .await;
+
+    match insert_result {
+        Ok(row) => {
+            log!(
+                Level::Info,
+                "Account {} ({}) created by account {}",
+                params.username,
+                row.id,
+                auth.account_id
+            );
+            Ok(auth.response(()))
+        }
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            return_early(ApiError::UsernameTaken)
+        }
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error inserting account: {}",
+            err
+        ))),
+    }
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn update_account(
+    id: String,
+    params: AccountCallParams,
+) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+    let account_id = match parse_account_id(&id) {
+        Err(err) => return return_early(err),
+        Ok(account_id) => account_id,
+    };
+    let params = match params.validated() {
+        Err(_) => {
+            log!(Level::Warn, "Invalid account params");
+            return return_early(ApiError::UnexpectedError(
+                "Invalid account params".to_string(),
+            ));
+        }
+        Ok(params) => params,
+    };
+    // there has to be an admin left to undo it
+    if account_id == auth.account_id && !params.role.includes(Role::Admin) {
+        return return_early(ApiError::UnexpectedError(
+            "Admins can't take away their own admin role".to_string(),
+        ));
+    }
+
+    let update_result = query!(
+        "\
+            UPDATE account \
+            SET username = $2, name = $3, email = $4, preferred_language = $5, role = $6 \
+            WHERE id = $1\
+        ",
+        account_id,
+        params.username,
+        params.name,
+        params.email(),
+        params.preferred_language.clone() as Language,
+        params.role as Role
+    )
+    .execute(&**db_pool)
+    .await;
+
+    match update_result {
+        Ok(result) if result.rows_affected() == 0 => {
+            return_early(ApiError::UnexpectedError("Unknown account".to_string()))
+        }
+        Ok(_) => {
+            log!(
+                Level::Info,
+                "Account {} updated by account {}",
+                account_id,
+                auth.account_id
+            );
+            Ok(auth.response(()))
+        }
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+            return_early(ApiError::UsernameTaken)
+        }
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error updating account: {}",
+            err
+        ))),
+    }
+}
+
+/// Disables or re-enables an account. Disabling ends all its sessions and pending logins.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn set_account_disabled(
+    id: String,
+    disabled: bool,
+) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use chrono::Utc;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+    let account_id = match parse_account_id(&id) {
+        Err(err) => return return_early(err),
+        Ok(account_id) => account_id,
+    };
+    if account_id == auth.account_id {
+        return return_early(ApiError::UnexpectedError(
+            "Admins can't disable their own account".to_string(),
+        ));
+    }
+    let disabled_at = if disabled {
+        Some(Utc::now().naive_utc())
+    } else {
+        None
+    };
+
+    let disable_result = async {
+        let mut tx = db_pool.begin().await?;
+        query!(
+            "\
+                UPDATE account \
+                SET disabled_at = $2 \
+                WHERE id = $1\
+            ",
+            account_id,
+            disabled_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        if disabled {
+            query!(
+                "\
+                    DELETE FROM login_challenge \
+                    WHERE account_id = $1\
+                ",
+                account_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+    if disable_result.is_ok() && disabled {
+        if let Err(err) = session_store.revoke_all(account_id, None).await {
+            return return_early(ApiError::DbError(err.to_string()));
+        }
+    }
+
+    match disable_result {
+        Ok(_) => {
+            log!(
+                Level::Info,
+                "Account {} {} by account {}",
+                account_id,
+                if disabled { "disabled" } else { "enabled" },
+                auth.account_id
+            );
+            Ok(auth.response(()))
+        }
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error disabling account: {}",
+            err
+        ))),
+    }
+}
+
+/// Deletes an account and its sessions, its tokens are deleted by cascade.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn delete_account(id: String) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+    let account_id = match parse_account_id(&id) {
+        Err(err) => return return_early(err),
+        Ok(account_id) => account_id,
+    };
+    if account_id == auth.account_id {
+        return return_early(ApiError::UnexpectedError(
+            "Admins can't delete their own account".to_string(),
+        ));
+    }
+
+    let delete_result = query!(
+        "\
+            DELETE FROM account \
+            WHERE id = $1 \
+            RETURNING username\
+        ",
+        account_id
+    )
+    .fetch_optional(&**db_pool)
+    .await;
+
+    match delete_result {
+        Ok(None) => return_early(ApiError::UnexpectedError("Unknown account".to_string())),
+        Ok(Some(row)) => {
+            // the session table cascades, other stores don't know the account
+            if let Err(err) = session_store.revoke_all(account_id, None).await {
+                log!(
+                    Level::Warn,
+                    "Error deleting sessions of deleted account: {}",
+                    err
+                );
+            }
+            log!(
+                Level::Info,
+                "Account {} ({}) deleted by account {}",
+                row.username,
+                account_id,
+                auth.account_id
+            );
+            Ok(auth.response(()))
+        }
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error deleting account: {}",
+            err
+        ))),
+    }
+}
+
+/// Sets a new password for another account, ending its sessions and lifting a login lock.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn reset_account_password(
+    id: String,
+    new_password: String,
+) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use bcrypt::{hash, DEFAULT_COST};
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+    let account_id = match parse_account_id(&id) {
+        Err(err) => return return_early(err),
+        Ok(account_id) => account_id,
+    };
+    // the own password is changed knowing the current one
+    if account_id == auth.account_id {
+        return return_early(ApiError::UnexpectedError(
+            "Use the change password page for the own account".to_string(),
+        ));
+    }
+    if !length_validated(&new_password, PASSWORD_MAX_LENGTH) {
+        return return_early(ApiError::UnexpectedError(
+            "Invalid password params".to_string(),
+        ));
+    }
+    let pw_hash = match hash(&new_password, DEFAULT_COST) {
+        Err(err) => {
+            log!(Level::Error, "Error hashing password: {}", err);
+            return return_early(ApiError::UnexpectedError("Hashing Error".to_string()));
+        }
+        Ok(pw_hash) => pw_hash,
+    };
+
+    let reset_result = query!(
+        "\
+            UPDATE account \
+            SET pw_hash = $1, failed_logins = 0, failed_login_window_start = NULL, \
+                locked_until = NULL \
+            WHERE id = $2\
+        ",
+        pw_hash,
+        account_id
+    )
+    .execute(&**db_pool)
+    .await
+    .map(|result| result.rows_affected());
+    // whoever knew the old password mustn't stay logged in
+    if let Ok(1..) = reset_result {
+        if let Err(err) = session_store.revoke_all(account_id, None).await {
+            return return_early(ApiError::DbError(err.to_string()));
+        }
+    }
+
+    match reset_result {
+        Ok(0) => return_early(ApiError::UnexpectedError("Unknown account".to_string())),
+        Ok(_) => {
+            log!(
+                Level::Info,
+                "Password of account {} reset by account {}",
+                account_id,
+                auth.account_id
+            );
+            Ok(auth.response(()))
+        }
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error resetting password: {}",
+            err
+        ))),
+    }
+}
+
+/// Starts a session for another account, so an admin sees the app exactly as its user does.
+///
+/// The admin's own session ends, `stop_impersonation` starts a new one.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn impersonate_account(id: String) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::pages::login::insert_session;
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+    if let Err(err) = auth.require_role(Role::Admin) {
+        return return_early(err);
+    }
+    let account_id = match parse_account_id(&id) {
+        Err(err) => return return_early(err),
+        Ok(account_id) => account_id,
+    };
+
+    let account_row = match query!(
+        "\
+            SELECT username, role as \"role: Role\", disabled_at \
+            FROM account \
+            WHERE id = $1\
+        ",
+        account_id
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting account: {}",
+                err
+            )))
+        }
+        Ok(None) => return return_early(ApiError::UnexpectedError("Unknown account".to_string())),
+        Ok(Some(row)) => row,
+    };
+    // an impersonated admin could impersonate further, hiding who acts
+    if account_row.role.includes(Role::Admin) {
+        return return_early(ApiError::UnexpectedError(
+            "Admins can't be impersonated".to_string(),
+        ));
+    }
+    if account_row.disabled_at.is_some() {
+        return return_early(ApiError::AccountDisabled);
+    }
+
+    let (token, expires_at) = match insert_session(account_id, Some(auth.account_id)).await {
+        Err(err) => return return_early(err),
+        Ok(session) => session,
+    };
+    if let Err(err) = session_store.revoke(auth.session_id, auth.account_id).await {
+        log!(
+            Level::Warn,
+            "Error ending the impersonator's session: {}",
+            err
+        );
+    }
+    log!(
+        Level::Info,
+        "Impersonation of account {} ({}) started by account {}",
+        account_row.username,
+        account_id,
+        auth.account_id
+    );
+
+    Ok(ApiResponse {
+        error: None,
+        expires_at,
+        token,
+        data: (),
+    })
+}
@@ -0,0 +1,469 @@
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::model::api_token::ApiToken;
+use crate::pages::login::{length_validated, show_error};
+use crate::utils::{csrf_field, format_timestamp, set_login_data_to_session_storage};
+use leptos::ev;
+use leptos::form::ActionForm;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use leptos::tachys::html::event;
+use leptos::{component, server, IntoView};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlFormElement, SubmitEvent};
+
+/// The length of `api_token.name`.
+const TOKEN_NAME_MAX_LENGTH: u8 = 50;
+/// The choices for the lifetime of a new token, 0 for one without expiry.
+const EXPIRY_DAYS_OPTIONS: [u16; 4] = [30, 90, 365, 0];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CreateApiTokenCallParams {
+    name: String,
+    expires_in_days: u16,
+}
+
+impl CreateApiTokenCallParams {
+    fn validated(&self) -> Result<CreateApiTokenCallParams, ()> {
+        if !length_validated(&self.name, TOKEN_NAME_MAX_LENGTH) {
+            return Err(());
+        };
+        if !EXPIRY_DAYS_OPTIONS.contains(&self.expires_in_days) {
+            return Err(());
+        };
+
+        Ok(self.clone())
+    }
+}
+
+/// The profile page section to manage personal API tokens for scripts calling the server fns.
+#[component]
+pub fn ApiTokens() -> impl IntoView {
+    let i18n = use_i18n();
+    let api_tokens = LocalResource::new(list_api_tokens);
+    let create_api_token = ServerAction::<CreateApiToken>::new();
+
+    Effect::new(move || {
+        if let Some(Ok(response)) = api_tokens.get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+    Effect::new(move || {
+        if let Some(Ok(response)) = create_api_token.value().get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                api_tokens.refetch();
+            }
+        }
+    });
+
+    let revoke = move |id: String| {
+        spawn_local(async move {
+            let _ = revoke_api_token(id).await;
+            api_tokens.refetch();
+        });
+    };
+
+    // the new token can't be shown again, as only its hash is stored
+    let message = move || match create_api_token.value().get() {
+        Some(Ok(response)) => match response.error {
+            None => div()
+                .class("alert alert-success")
+                .child(({ p().child(t!(i18n, apiTokenCreated)) }, {
+                    code().child(response.data)
+                }))
+                .into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+        None => div().hidden(true).into_any(),
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match CreateApiToken::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        if data.params.validated().is_err() {
+            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+            show_error(&ev, &form, 0);
+        }
+    };
+
+    let token_list = move || match api_tokens.get() {
+        None => div()
+            .class("text-center")
+            .child(
+                div()
+                    .class("spinner-border")
+                    .role("status")
+                    .child(span().class("visually-hidden").child(t!(i18n, loading))),
+            )
+            .into_any(),
+        Some(Ok(response)) => match response.error {
+            None => api_token_table(response.data, revoke).into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+    };
+
+    section().class("mt-4").child((
+        { h2().child(t![i18n, apiTokens]) },
+        { p().child(t![i18n, apiTokensIntro]) },
+        { div().child(token_list) },
+        {
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(create_api_token)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref11")
+                                        .child(t![i18n, apiTokenName])
+                                },
+                                {
+                                    input()
+                                        .r#type("text")
+                                        .class("form-control")
+                                        .id("ref11")
+                                        .name("params[name]")
+                                        .required(true)
+                                        .maxlength(TOKEN_NAME_MAX_LENGTH as i64)
+                                },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, apiTokenNameRequired))
+                                },
+                            )),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref12")
+                                        .child(t![i18n, apiTokenExpiry])
+                                },
+                                {
+                                    select()
+                                        .class("form-select")
+                                        .id("ref12")
+                                        .name("params[expires_in_days]")
+                                        .child(
+                                            EXPIRY_DAYS_OPTIONS
+                                                .iter()
+                                                .map(|days| {
+                                                    option().value(days.to_string()).child(
+                                                        if *days == 0 {
+                                                            t![i18n, apiTokenNoExpiry].into_any()
+                                                        } else {
+                                                            t![
+                                                                i18n,
+                                                                apiTokenExpiryDays,
+                                                                days = *days
+                                                            ]
+                                                            .into_any()
+                                                        },
+                                                    )
+                                                })
+                                                .collect::<Vec<_>>(),
+                                        )
+                                },
+                            )),
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-primary")
+                                    .child(t![i18n, createApiToken])
+                            },
+                            { div().class("mt-2").child(message) },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            ))
+        },
+    ))
+}
+
+fn api_token_table(
+    api_tokens: Vec<ApiToken>,
+    revoke: impl Fn(String) + Copy + 'static,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let rows = api_tokens
+        .into_iter()
+        .map(|api_token| {
+            let id = api_token.id.clone();
+            tr().child((
+                { td().child(api_token.name) },
+                { td().child(format_timestamp(api_token.created_at)) },
+                {
+                    td().child(match api_token.last_used_at {
+                        None => t![i18n, apiTokenNeverUsed].into_any(),
+                        Some(last_used_at) => format_timestamp(last_used_at).into_any(),
+                    })
+                },
+                {
+                    td().child(match api_token.expires_at {
+                        None => t![i18n, apiTokenNoExpiry].into_any(),
+                        Some(expires_at) => format_timestamp(expires_at).into_any(),
+                    })
+                },
+                {
+                    td().child(
+                        button()
+                            .r#type("button")
+                            .class("btn btn-outline-danger btn-sm")
+                            .on(ev::click, move |_| revoke(id.clone()))
+                            .child(t![i18n, revokeApiToken]),
+                    )
+                },
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    table().class("table").child((
+        {
+            thead().child(tr().child((
+                { th().attr("scope", "col").child(t![i18n, apiTokenName]) },
+                { th().attr("scope", "col").child(t![i18n, sessionCreated]) },
+                { th().attr("scope", "col").child(t![i18n, sessionLastUsed]) },
+                { th().attr("scope", "col").child(t![i18n, sessionExpires]) },
+                { th().attr("scope", "col") },
+            )))
+        },
+        { tbody().child(rows) },
+    ))
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn list_api_tokens() -> Result<ApiResponse<Vec<ApiToken>>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+
+    let api_token_rows = match query!(
+        "\
+            SELECT id, name, created_at, expires_at, last_used_at \
+            FROM api_token \
+            WHERE account_id = $1 \
+            ORDER BY created_at DESC\
+        ",
+        auth.account_id
+    )
+    .fetch_all(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting API tokens: {}",
+                err
+            )))
+        }
+        Ok(rows) => rows,
+    };
+
+    Ok(auth.response(
+        api_token_rows
+            .into_iter()
+            .map(|row| ApiToken {
+                id: row.id.to_string(),
+                name: row.name,
+                created_at: row.created_at.and_utc().timestamp(),
+                expires_at: row
+                    .expires_at
+                    .map(|expires_at| expires_at.and_utc().timestamp()),
+                last_used_at: row
+                    .last_used_at
+                    .map(|last_used_at| last_used_at.and_utc().timestamp()),
+            })
+            .collect(),
+    ))
+}
+
+/// Creates a personal API token and returns it, this is the only time it's shown.
+///
+/// Only the account holder's own login session may create tokens, not an API token or an
+/// impersonating admin.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn create_api_token(
+    params: CreateApiTokenCallParams,
+) -> Result<ApiResponse<String>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use crate::server_utils::token::generate_api_token;
+    use actix_web::web::Data;
+    use chrono::{TimeDelta, Utc};
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_own_session() {
+        return return_early(err);
+    }
+    let params = match params.validated() {
+        Err(_) => {
+            log!(Level::Warn, "Invalid API token params");
+            return return_early(ApiError::UnexpectedError(
+                "Invalid API token params".to_string(),
+            ));
+        }
+        Ok(params) => params,
+    };
+    let (api_token, api_token_hash) = match generate_api_token() {
+        Err(err) => {
+            log!(Level::Error, "Error generating API token: {}", err);
+            return return_early(ApiError::UnexpectedError("Random Error".to_string()));
+        }
+        Ok(api_token) => api_token,
+    };
+    let expires_at = if params.expires_in_days == 0 {
+        None
+    } else {
+        Some(Utc::now().naive_utc() + TimeDelta::days(params.expires_in_days as i64))
+    };
+
+    match query!(
+        "\
+            INSERT INTO api_token (account_id, name, token_hash, expires_at) \
+            VALUES ($1, $2, $3, $4) \
+            RETURNING id\
+        ",
+        auth.account_id,
+        params.name,
+        api_token_hash,
+        expires_at
+    )
+    .fetch_one(&**db_pool)
+    .await
+    {
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error inserting API token: {}",
+            err
+        ))),
+        Ok(row) => {
+            log!(
+                Level::Info,
+                "API token {} created by account {}",
+                row.id,
+                auth.account_id
+            );
+            Ok(auth.response(api_token))
+        }
+    }
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn revoke_api_token(id: String) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::types::Uuid;
+    use sqlx::{Pool, Postgres};
+    use std::str::FromStr;
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_own_session() {
+        return return_early(err);
+    }
+    let api_token_id = match Uuid::from_str(&id) {
+        Err(_) => {
+            return return_early(ApiError::UnexpectedError(
+                "Invalid API token id".to_string(),
+            ))
+        }
+        Ok(api_token_id) => api_token_id,
+    };
+
+    // only the own tokens can be revoked
+    match query!(
+        "\
+            DELETE FROM api_token \
+            WHERE id = $1 AND account_id = $2\
+        ",
+        api_token_id,
+        auth.account_id
+    )
+    .execute(&**db_pool)
+    .await
+    {
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error deleting API token: {}",
+            err
+        ))),
+        Ok(_) => {
+            log!(
+                Level::Info,
+                "API token {} revoked by account {}",
+                api_token_id,
+                auth.account_id
+            );
+            Ok(auth.response(()))
+        }
+    }
+}
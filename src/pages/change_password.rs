@@ -0,0 +1,373 @@
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::pages::login::{length_validated, show_error, PASSWORD_MAX_LENGTH};
+use crate::utils::{csrf_field, format_timestamp, set_login_data_to_session_storage};
+use leptos::form::ActionForm;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::tachys::html::event;
+use leptos::{component, server, IntoView};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlFormElement, SubmitEvent};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChangePasswordCallParams {
+    current_password: String,
+    new_password: String,
+    new_password_repeated: String,
+    // an unchecked checkbox isn't sent at all
+    #[serde(default)]
+    revoke_other_sessions: bool,
+}
+
+enum ChangePasswordCallParamsError {
+    InvalidCurrentPassword,
+    InvalidNewPassword,
+    PasswordsDiffer,
+}
+
+impl ChangePasswordCallParams {
+    fn validated(&self) -> Result<ChangePasswordCallParams, ChangePasswordCallParamsError> {
+        if !length_validated(&self.current_password, PASSWORD_MAX_LENGTH) {
+            return Err(ChangePasswordCallParamsError::InvalidCurrentPassword);
+        };
+        if !length_validated(&self.new_password, PASSWORD_MAX_LENGTH) {
+            return Err(ChangePasswordCallParamsError::InvalidNewPassword);
+        };
+        if self.new_password != self.new_password_repeated {
+            return Err(ChangePasswordCallParamsError::PasswordsDiffer);
+        };
+
+        Ok(self.clone())
+    }
+}
+
+#[component]
+pub fn ChangePassword() -> impl IntoView {
+    let i18n = use_i18n();
+    let change_password = ServerAction::<ChangePassword>::new();
+
+    Effect::new(move || {
+        if let Some(Ok(response)) = change_password.value().get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+
+    let message = move || {
+        let value = change_password.value().get();
+        let pending = change_password.pending().get();
+
+        if pending {
+            return div()
+                .class("text-center")
+                .child(
+                    div()
+                        .class("spinner-border")
+                        .role("status")
+                        .child(span().class("visually-hidden").child(t!(i18n, loading))),
+                )
+                .into_any();
+        }
+
+        match value {
+            Some(Ok(response)) => match response.error {
+                None => div()
+                    .class("alert alert-success")
+                    .child(t!(i18n, passwordChanged))
+                    .into_any(),
+                Some(error) => {
+                    let error_message = match error {
+                        ApiError::InvalidCredentials => t!(i18n, wrongCurrentPassword).into_any(),
+                        ApiError::AccountLocked(until) => {
+                            t!(i18n, accountLocked, until = format_timestamp(until)).into_any()
+                        }
+                        _ => t!(i18n, serverError, error = "").into_any(),
+                    };
+                    div()
+                        .class("alert alert-danger")
+                        .child(error_message)
+                        .into_any()
+                }
+            },
+            Some(Err(err)) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+            None => div().hidden(true).into_any(),
+        }
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match ChangePassword::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+        match data.validated() {
+            Ok(_) => {}
+            Err(error) => match error {
+                ChangePasswordCallParamsError::InvalidCurrentPassword => {
+                    show_error(&ev, &form, 0);
+                }
+                ChangePasswordCallParamsError::InvalidNewPassword => {
+                    show_error(&ev, &form, 1);
+                }
+                ChangePasswordCallParamsError::PasswordsDiffer => {
+                    show_error(&ev, &form, 2);
+                }
+            },
+        }
+    };
+
+    let password_input = move |id: &'static str, name: &'static str| {
+        input()
+            .r#type("password")
+            .class("form-control")
+            .id(id)
+            .name(name)
+            .required(true)
+            .maxlength(PASSWORD_MAX_LENGTH as i64)
+    };
+
+    div()
+        .class("container")
+        .child(({ h1().child(t![i18n, changePassword]) }, {
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(change_password)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref1")
+                                        .child(t![i18n, currentPassword])
+                                },
+                                { password_input("ref1", "params[current_password]") },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, passwordRequired))
+                                },
+                            )),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref2")
+                                        .child(t![i18n, newPassword])
+                                },
+                                { password_input("ref2", "params[new_password]") },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, passwordRequired))
+                                },
+                            )),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref3")
+                                        .child(t![i18n, newPasswordRepeated])
+                                },
+                                { password_input("ref3", "params[new_password_repeated]") },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, passwordsDiffer))
+                                },
+                            )),
+                            div().class("mb-3 form-check").child((
+                                {
+                                    input()
+                                        .r#type("checkbox")
+                                        .class("form-check-input")
+                                        .id("ref4")
+                                        .name("params[revoke_other_sessions]")
+                                        .value("true")
+                                },
+                                {
+                                    label()
+                                        .class("form-check-label")
+                                        .r#for("ref4")
+                                        .child(t![i18n, revokeOtherSessions])
+                                },
+                            )),
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-primary")
+                                    .child(t![i18n, changePassword])
+                            },
+                            { div().class("mt-2").child(message) },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            ))
+        }))
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn change_password(
+    params: ChangePasswordCallParams,
+) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::server_utils::configuration::LoginLockoutSettings;
+    use crate::server_utils::login_lockout::{record_failed_login, reset_failed_logins};
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use bcrypt::{hash, verify, DEFAULT_COST};
+    use chrono::Utc;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let lockout_settings = match use_context::<Data<LoginLockoutSettings>>() {
+        None => {
+            log!(Level::Warn, "No login lockout settings found in context");
+            return return_early(ApiError::UnexpectedError("Configuration Error".to_string()));
+        }
+        Some(lockout_settings) => lockout_settings,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_own_session() {
+        return return_early(err);
+    }
+    let params = match params.validated() {
+        Err(_) => {
+            log!(Level::Warn, "Invalid change password params");
+            return return_early(ApiError::UnexpectedError(
+                "Invalid password params".to_string(),
+            ));
+        }
+        Ok(params) => params,
+    };
+
+    let account_row = match query!(
+        "\
+            SELECT pw_hash, locked_until \
+            FROM account \
+            WHERE id = $1\
+        ",
+        auth.account_id
+    )
+    .fetch_one(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting account: {}",
+                err
+            )))
+        }
+        Ok(row) => row,
+    };
+    // guessing the current password counts like failed logins
+    if let Some(locked_until) = account_row.locked_until {
+        let locked_until = locked_until.and_utc().timestamp();
+        if locked_until > Utc::now().timestamp() {
+            log!(
+                Level::Info,
+                "Password change of locked account {}",
+                auth.account_id
+            );
+            return return_early(ApiError::AccountLocked(locked_until));
+        }
+    }
+    let verified = verify(&params.current_password, &account_row.pw_hash).unwrap_or_else(|e| {
+        log!(Level::Warn, "Error verifying password: {}", e);
+        false
+    });
+    if !verified {
+        return match record_failed_login(&db_pool, auth.account_id, &lockout_settings).await {
+            Ok(None) => return_early(ApiError::InvalidCredentials),
+            Ok(Some(locked_until)) => {
+                log!(Level::Warn, "Locked account {}", auth.account_id);
+                return_early(ApiError::AccountLocked(locked_until.and_utc().timestamp()))
+            }
+            Err(err) => return_early(ApiError::DbError(format!(
+                "Error recording failed login: {}",
+                err
+            ))),
+        };
+    }
+    if let Err(err) = reset_failed_logins(&db_pool, auth.account_id).await {
+        return return_early(ApiError::DbError(format!(
+            "Error resetting failed logins: {}",
+            err
+        )));
+    }
+    let pw_hash = match hash(&params.new_password, DEFAULT_COST) {
+        Err(err) => {
+            log!(Level::Error, "Error hashing password: {}", err);
+            return return_early(ApiError::UnexpectedError("Hashing Error".to_string()));
+        }
+        Ok(pw_hash) => pw_hash,
+    };
+    if let Err(err) = query!(
+        "\
+            UPDATE account \
+            SET pw_hash = $1 \
+            WHERE id = $2\
+        ",
+        pw_hash,
+        auth.account_id
+    )
+    .execute(&**db_pool)
+    .await
+    {
+        return return_early(ApiError::DbError(format!(
+            "Error updating password: {}",
+            err
+        )));
+    }
+    log!(
+        Level::Info,
+        "Changed password of account {}",
+        auth.account_id
+    );
+
+    if params.revoke_other_sessions {
+        let session_store =
+            use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+        if let Err(err) = session_store
+            .revoke_all(auth.account_id, Some(auth.session_id))
+            .await
+        {
+            return return_early(ApiError::DbError(err.to_string()));
+        }
+        log!(
+            Level::Info,
+            "Revoked other sessions of account {}",
+            auth.account_id
+        );
+    }
+
+    Ok(auth.response(()))
+}
@@ -0,0 +1,16 @@
+use crate::i18n::*;
+use leptos::html::{div, h1, p, ElementChild};
+use leptos::prelude::ClassAttribute;
+use leptos::{component, IntoView};
+
+/// Shown instead of a page the logged-in user's role doesn't allow.
+#[component]
+pub fn Forbidden() -> impl IntoView {
+    let i18n = use_i18n();
+
+    div()
+        .class("container")
+        .child(({ h1().child(t![i18n, forbidden]) }, {
+            p().child(t![i18n, forbiddenText])
+        }))
+}
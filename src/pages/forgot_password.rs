@@ -0,0 +1,273 @@
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::pages::login::{length_validated, show_error, USERNAME_MAX_LENGTH};
+use leptos::form::ActionForm;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::tachys::html::event;
+use leptos::{component, server, IntoView};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlFormElement, SubmitEvent};
+
+#[component]
+pub fn ForgotPassword() -> impl IntoView {
+    let i18n = use_i18n();
+    let request_password_reset = ServerAction::<RequestPasswordReset>::new();
+
+    let message = move || {
+        let value = request_password_reset.value().get();
+        let pending = request_password_reset.pending().get();
+
+        if pending {
+            return div()
+                .class("text-center")
+                .child(
+                    div()
+                        .class("spinner-border")
+                        .role("status")
+                        .child(span().class("visually-hidden").child(t!(i18n, loading))),
+                )
+                .into_any();
+        }
+
+        match value {
+            Some(Ok(response)) => match response.error {
+                // doesn't tell whether the account exists
+                None => div()
+                    .class("alert alert-success")
+                    .child(t!(i18n, resetLinkSent))
+                    .into_any(),
+                Some(err) => div()
+                    .class("alert alert-danger")
+                    .child(t!(i18n, serverError, error = err.to_string()))
+                    .into_any(),
+            },
+            Some(Err(err)) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+            None => div().hidden(true).into_any(),
+        }
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match RequestPasswordReset::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        if !length_validated(&data.username, USERNAME_MAX_LENGTH) {
+            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+            show_error(&ev, &form, 0);
+        }
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, forgotPassword]) },
+        { p().child(t![i18n, forgotPasswordIntro]) },
+        {
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(request_password_reset)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref1")
+                                        .child(t![i18n, username])
+                                },
+                                {
+                                    input()
+                                        .r#type("text")
+                                        .class("form-control")
+                                        .id("ref1")
+                                        .name("username")
+                                },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, usernameRequired))
+                                },
+                            )),
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-primary")
+                                    .child(t![i18n, sendResetLink])
+                            },
+                            { div().class("mt-2").child(message) },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            ))
+        },
+        { a().href("/login").child(t![i18n, backToLogin]) },
+    ))
+}
+
+/// Sends a password reset link to the mail address of the account with the given username.
+///
+/// Always succeeds, if nothing unexpected happens, so it can't be used to find out
+/// which usernames exist or have a mail address.
+#[server]
+pub async fn request_password_reset(username: String) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::error::{return_early, ApiError};
+    use crate::model::language::Language;
+    use crate::server_utils::configuration::PasswordResetSettings;
+    use crate::server_utils::mailer::{Mail, Mailer};
+    use crate::server_utils::password_reset::reset_mail_text;
+    use crate::server_utils::token::generate_token;
+    use actix_web::web::Data;
+    use chrono::{TimeDelta, Utc};
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let mailer = match use_context::<Data<dyn Mailer>>() {
+        None => {
+            log!(Level::Warn, "No mailer found in context");
+            return return_early(ApiError::UnexpectedError("Configuration Error".to_string()));
+        }
+        Some(mailer) => mailer,
+    };
+    let settings = match use_context::<Data<PasswordResetSettings>>() {
+        None => {
+            log!(Level::Warn, "No password reset settings found in context");
+            return return_early(ApiError::UnexpectedError("Configuration Error".to_string()));
+        }
+        Some(settings) => settings,
+    };
+    let done = ApiResponse {
+        expires_at: 0,
+        token: "".to_string(),
+        error: None,
+        data: (),
+    };
+    if !length_validated(&username, USERNAME_MAX_LENGTH) {
+        log!(Level::Warn, "Invalid password reset username");
+        return Ok(done);
+    }
+
+    let account_row = match query!(
+        "\
+            SELECT id, name, email, preferred_language as \"preferred_language: Language\" \
+            FROM account \
+            WHERE username = $1\
+        ",
+        username
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting account: {}",
+                err
+            )))
+        }
+        Ok(None) => {
+            log!(Level::Info, "Password reset for unknown user: {}", username);
+            return Ok(done);
+        }
+        Ok(Some(row)) => row,
+    };
+    let email = match account_row.email {
+        None => {
+            log!(
+                Level::Warn,
+                "Password reset for user without mail: {}",
+                username
+            );
+            return Ok(done);
+        }
+        Some(email) => email,
+    };
+    let (token, token_hash) = match generate_token() {
+        Err(err) => {
+            log!(
+                Level::Error,
+                "Error generating password reset token: {}",
+                err
+            );
+            return return_early(ApiError::UnexpectedError("Random Error".to_string()));
+        }
+        Ok(token) => token,
+    };
+    let expires_at = Utc::now().naive_utc() + TimeDelta::minutes(settings.token_expiry_mins as i64);
+
+    let insert_result = async {
+        let mut tx = db_pool.begin().await?;
+        // only the latest link is valid
+        query!(
+            "\
+                DELETE FROM password_reset_token \
+                WHERE account_id = $1 AND used_at IS NULL\
+            ",
+            account_row.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        query!(
+            "\
+                INSERT INTO password_reset_token (account_id, token_hash, expires_at) \
+                VALUES ($1, $2, $3)\
+            ",
+            account_row.id,
+            token_hash,
+            expires_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await;
+    if let Err(err) = insert_result {
+        return return_early(ApiError::DbError(format!(
+            "Error inserting password reset token: {}",
+            err
+        )));
+    }
+
+    let link = format!(
+        "{}/reset-password?token={}",
+        settings.link_base_url.trim_end_matches('/'),
+        token
+    );
+    let (subject, body) = reset_mail_text(
+        &account_row.preferred_language,
+        &account_row.name,
+        &link,
+        settings.token_expiry_mins,
+    );
+    let mailer = mailer.into_inner();
+    // sending in the background doesn't reveal by timing whether a mail is sent
+    tokio::spawn(async move {
+        let mail = Mail {
+            to: email,
+            subject,
+            body,
+        };
+        match mailer.send(mail).await {
+            Err(err) => log!(Level::Error, "Error sending password reset mail: {}", err),
+            Ok(_) => log!(Level::Info, "Sent password reset mail to: {}", username),
+        }
+    });
+
+    Ok(done)
+}
@@ -1,10 +1,11 @@
 use crate::api::error::ApiError;
 use crate::api::response::ApiResponse;
 use crate::i18n::*;
+use crate::model::two_factor::TotpChallenge;
 use crate::model::user::User;
-use crate::utils::{
-    get_lang, set_lang_to_i18n, set_lang_to_locale_storage, set_login_data_to_session_storage,
-};
+use crate::pages::oidc_login::oidc_login_button;
+use crate::utils::{format_timestamp, get_lang, set_login_data_to_session_storage, set_user_lang};
+use leptos::ev;
 use leptos::form::ActionForm;
 use leptos::html::*;
 use leptos::prelude::*;
@@ -17,8 +18,16 @@ use serde::{Deserialize, Serialize};
 use wasm_bindgen::JsCast;
 use web_sys::{HtmlFormElement, SubmitEvent};
 
-const USERNAME_MAX_LENGTH: u8 = 20;
-const PASSWORD_MAX_LENGTH: u8 = 32;
+pub(crate) const USERNAME_MAX_LENGTH: u8 = 20;
+pub(crate) const PASSWORD_MAX_LENGTH: u8 = 32;
+pub(crate) const NAME_MAX_LENGTH: u8 = 80;
+#[cfg(feature = "ssr")]
+const USER_AGENT_MAX_LENGTH: usize = 255;
+/// The length of a hex encoded login challenge.
+const CHALLENGE_LENGTH: usize = 64;
+const TOTP_CODE_LENGTH: usize = 6;
+/// The length of a recovery code without its dash.
+const RECOVERY_CODE_LENGTH: usize = 10;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LoginCallParams {
@@ -33,27 +42,57 @@ enum LoginCallParamsError {
 
 impl LoginCallParams {
     fn validated(&self) -> Result<LoginCallParams, LoginCallParamsError> {
-        if !self.length_validated(&self.username, USERNAME_MAX_LENGTH) {
+        if !length_validated(&self.username, USERNAME_MAX_LENGTH) {
             return Err(LoginCallParamsError::InvalidUsername);
         };
-        if !self.length_validated(&self.password, PASSWORD_MAX_LENGTH) {
+        if !length_validated(&self.password, PASSWORD_MAX_LENGTH) {
             return Err(LoginCallParamsError::InvalidPassword);
         };
 
         Ok(self.clone())
     }
+}
 
-    fn length_validated(&self, input: &String, max_size: u8) -> bool {
-        let graphemes_length_as_u8 = self.graphems_length_u8(input.chars().count());
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VerifyTotpCallParams {
+    challenge: String,
+    code: String,
+}
 
-        !(input.is_empty() || graphemes_length_as_u8 > max_size)
-    }
+impl VerifyTotpCallParams {
+    /// Accepts a TOTP code or a recovery code, both with or without spaces and dashes.
+    fn validated(&self) -> Result<VerifyTotpCallParams, ()> {
+        if self.challenge.len() != CHALLENGE_LENGTH {
+            return Err(());
+        };
+        let code = self
+            .code
+            .chars()
+            .filter(|char| !char.is_whitespace() && *char != '-')
+            .collect::<String>();
+        let is_totp_code =
+            code.len() == TOTP_CODE_LENGTH && code.chars().all(|char| char.is_ascii_digit());
+        let is_recovery_code = code.len() == RECOVERY_CODE_LENGTH
+            && code.chars().all(|char| char.is_ascii_alphanumeric());
+        if !(is_totp_code || is_recovery_code) {
+            return Err(());
+        };
 
-    fn graphems_length_u8(&self, usize: usize) -> u8 {
-        <usize as TryInto<u8>>::try_into(usize).unwrap_or_else(|_| u8::MAX)
+        Ok(self.clone())
     }
 }
 
+/// Checks that the input is neither empty nor longer than `max_size` characters.
+pub(crate) fn length_validated(input: &str, max_size: u8) -> bool {
+    let graphemes_length_as_u8 = graphems_length_u8(input.chars().count());
+
+    !(input.is_empty() || graphemes_length_as_u8 > max_size)
+}
+
+fn graphems_length_u8(usize: usize) -> u8 {
+    <usize as TryInto<u8>>::try_into(usize).unwrap_or(u8::MAX)
+}
+
 #[component]
 pub fn Login(
     set_user: WriteSignal<Option<User>>,
@@ -66,46 +105,40 @@ pub fn Login(
         .get_untracked()
         .get("orig_url")
         .unwrap_or_else(|| "/".to_string());
+    let oidc_orig_url = orig_url.clone();
     let navigate = use_navigate();
+    let verify_totp = ServerAction::<VerifyTotp>::new();
+    // set if the password was right, but the account needs a second factor
+    let (totp_challenge, set_totp_challenge) = signal(None::<String>);
+
+    let complete_login = move |token: String, expires_at: i64| {
+        complete_login(
+            token,
+            expires_at,
+            orig_url.clone(),
+            set_user,
+            lang,
+            lang_setter,
+            navigate.clone(),
+        )
+    };
+    let complete_login_clone = complete_login.clone();
 
     Effect::new(move || {
         if let Some(Ok(response)) = login.value().get() {
             if response.error.is_none() {
-                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
-                let navigate = navigate.clone();
-
-                // make sure param orig_url contains no '//' to prevent url injection
-                let validated_orig_url = if orig_url.contains("//") {
-                    "/".to_string()
-                } else {
-                    orig_url.clone()
-                };
+                match response.data {
+                    Some(challenge) => set_totp_challenge.set(Some(challenge.challenge)),
+                    None => complete_login(response.token, response.expires_at),
+                }
+            }
+        }
+    });
 
-                spawn_local(async move {
-                    if let Ok(res) = get_user().await {
-                        let server_lang = res.data.preferred_language;
-                        // shouldn't rerun on changes of lang
-                        let user_lang = lang.get_untracked();
-                        if server_lang != user_lang {
-                            lang_setter.set(server_lang.clone());
-                            set_lang_to_locale_storage(&server_lang);
-                            set_lang_to_i18n(&server_lang);
-                        }
-                        set_user.set(Some(User {
-                            name: res.data.name,
-                            preferred_language: server_lang,
-                        }));
-                        navigate(
-                            &validated_orig_url,
-                            NavigateOptions {
-                                resolve: false,
-                                replace: false,
-                                scroll: true,
-                                state: Default::default(),
-                            },
-                        );
-                    }
-                })
+    Effect::new(move || {
+        if let Some(Ok(response)) = verify_totp.value().get() {
+            if response.error.is_none() {
+                complete_login_clone(response.token, response.expires_at);
             }
         }
     });
@@ -127,15 +160,27 @@ pub fn Login(
         }
 
         match value {
-            Some(Ok(response)) => {
-                if response.error.is_none() {
-                    return div()
+            Some(Ok(response)) => match response.error {
+                None => {
+                    // the second factor is asked for by the TOTP form
+                    if response.data.is_some() {
+                        return div().hidden(true).into_any();
+                    }
+                    div()
                         .class("alert alert-success")
                         .child(t!(i18n, redirecting))
-                        .into_any();
-                } else {
-                    let error_message = match response.error.unwrap() {
+                        .into_any()
+                }
+                Some(error) => {
+                    let error_message = match error {
                         ApiError::InvalidCredentials => t!(i18n, invalidCredentials).into_any(),
+                        ApiError::AccountDisabled => t!(i18n, accountDisabled).into_any(),
+                        ApiError::AccountLocked(until) => {
+                            t!(i18n, accountLocked, until = format_timestamp(until)).into_any()
+                        }
+                        ApiError::RateLimited(retry_after) => {
+                            t!(i18n, rateLimited, seconds = retry_after).into_any()
+                        }
                         _ => t!(i18n, serverError, error = "").into_any(),
                     };
                     div()
@@ -143,7 +188,7 @@ pub fn Login(
                         .child(error_message)
                         .into_any()
                 }
-            }
+            },
             Some(Err(err)) => div()
                 .class("alert alert-danger")
                 .child(t!(i18n, serverError, error = err.to_string()))
@@ -153,85 +198,272 @@ pub fn Login(
     };
 
     let validated_on_client = move |ev: SubmitEvent| {
-        let data = Login::from_event(&ev);
-        if data.is_err() {
-            ev.prevent_default();
-        } else {
-            let data = data.unwrap().clone();
-            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
-            match data.validated() {
-                Ok(_) => {}
-                Err(error) => match error {
-                    LoginCallParamsError::InvalidUsername => {
-                        show_error(&ev, &form, 0);
-                    }
-                    LoginCallParamsError::InvalidPassword => {
-                        show_error(&ev, &form, 1);
-                    }
+        let data = match Login::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+        match data.validated() {
+            Ok(_) => {}
+            Err(error) => match error {
+                LoginCallParamsError::InvalidUsername => {
+                    show_error(&ev, &form, 0);
+                }
+                LoginCallParamsError::InvalidPassword => {
+                    show_error(&ev, &form, 1);
+                }
+            },
+        }
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, login]) },
+        {
+            div().hidden(move || totp_challenge.get().is_some()).child((
+                {
+                    ActionForm(
+                        ActionFormProps::builder()
+                            .action(login)
+                            .children(ToChildren::to_children(move || {
+                                (
+                                    div().class("mb-3 col-xs-1 col-xl-2").child((
+                                        {
+                                            label()
+                                                .class("form-label")
+                                                .r#for("ref1")
+                                                .child(t![i18n, username])
+                                        },
+                                        {
+                                            input()
+                                                .r#type("text")
+                                                .class("form-control")
+                                                .id("ref1")
+                                                .name("params[username]")
+                                        },
+                                        {
+                                            div()
+                                                .class("invalid-feedback")
+                                                .child(t!(i18n, usernameRequired))
+                                        },
+                                    )),
+                                    {
+                                        div().class("mb-3 col-xs-1 col-xl-2").child((
+                                            {
+                                                label()
+                                                    .class("form-label")
+                                                    .r#for("ref2")
+                                                    .child(t![i18n, password])
+                                            },
+                                            {
+                                                input()
+                                                    .r#type("password")
+                                                    .class("form-control")
+                                                    .id("ref2")
+                                                    .name("params[password]")
+                                                    .required(true)
+                                                    .maxlength(PASSWORD_MAX_LENGTH as i64)
+                                            },
+                                            {
+                                                div()
+                                                    .class("invalid-feedback")
+                                                    .child(t!(i18n, passwordRequired))
+                                            },
+                                        ))
+                                    },
+                                    {
+                                        button()
+                                            .r#type("submit")
+                                            .class("btn btn-primary")
+                                            .child(t![i18n, login])
+                                    },
+                                    { div().class("mt-2").child(message) },
+                                )
+                            }))
+                            .build(),
+                    )
+                    .attr("novalidate", "true")
+                    .add_any_attr(event::on(
+                        event::capture(event::submit),
+                        validated_on_client,
+                    ))
+                },
+                { a().href("/forgot-password").child(t![i18n, forgotPassword]) },
+                { oidc_login_button(oidc_orig_url) },
+            ))
+        },
+        { totp_form(verify_totp, totp_challenge, set_totp_challenge) },
+    ))
+}
+
+/// Stores the session of a finished login, restores the user and navigates to `orig_url`.
+///
+/// Shared by the password login and the single sign-on callback.
+pub(crate) fn complete_login(
+    token: String,
+    expires_at: i64,
+    orig_url: String,
+    set_user: WriteSignal<Option<User>>,
+    lang: ReadSignal<String>,
+    lang_setter: WriteSignal<String>,
+    navigate: impl Fn(&str, NavigateOptions) + 'static,
+) {
+    set_login_data_to_session_storage(token.as_str(), expires_at);
+
+    // make sure param orig_url contains no '//' to prevent url injection
+    let validated_orig_url = if orig_url.contains("//") {
+        "/".to_string()
+    } else {
+        orig_url
+    };
+
+    spawn_local(async move {
+        if let Ok(res) = get_user().await {
+            set_login_data_to_session_storage(res.token.as_str(), res.expires_at);
+            let server_lang = res.data.preferred_language;
+            set_user_lang(&server_lang, lang, lang_setter);
+            set_user.set(Some(User {
+                name: res.data.name,
+                preferred_language: server_lang,
+                role: res.data.role,
+                impersonator: res.data.impersonator,
+            }));
+            navigate(
+                &validated_orig_url,
+                NavigateOptions {
+                    resolve: false,
+                    replace: false,
+                    scroll: true,
+                    state: Default::default(),
                 },
+            );
+        }
+    })
+}
+
+/// The second login step for accounts with 2FA enabled.
+fn totp_form(
+    verify_totp: ServerAction<VerifyTotp>,
+    totp_challenge: ReadSignal<Option<String>>,
+    set_totp_challenge: WriteSignal<Option<String>>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+
+    let message = move || {
+        let value = verify_totp.value().get();
+        let pending = verify_totp.pending().get();
+
+        if pending {
+            return div()
+                .class("text-center")
+                .child(
+                    div()
+                        .class("spinner-border")
+                        .role("status")
+                        .child(span().class("visually-hidden").child(t!(i18n, loading))),
+                )
+                .into_any();
+        }
+
+        match value {
+            Some(Ok(response)) => match response.error {
+                None => div()
+                    .class("alert alert-success")
+                    .child(t!(i18n, redirecting))
+                    .into_any(),
+                Some(err) => {
+                    let error_message = match err {
+                        ApiError::InvalidTotpCode => t!(i18n, invalidTotpCode).into_any(),
+                        ApiError::Expired => t!(i18n, totpChallengeExpired).into_any(),
+                        ApiError::AccountLocked(until) => {
+                            t!(i18n, accountLocked, until = format_timestamp(until)).into_any()
+                        }
+                        ApiError::RateLimited(retry_after) => {
+                            t!(i18n, rateLimited, seconds = retry_after).into_any()
+                        }
+                        _ => t!(i18n, serverError, error = "").into_any(),
+                    };
+                    div()
+                        .class("alert alert-danger")
+                        .child(error_message)
+                        .into_any()
+                }
+            },
+            Some(Err(err)) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+            None => div().hidden(true).into_any(),
+        }
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match VerifyTotp::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
             }
+            Ok(data) => data,
+        };
+        if data.validated().is_err() {
+            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+            show_error(&ev, &form, 0);
         }
     };
 
-    div()
-        .class("container")
-        .child(({ h1().child(t![i18n, login]) }, {
+    div().hidden(move || totp_challenge.get().is_none()).child((
+        { p().child(t![i18n, totpLoginIntro]) },
+        {
             ActionForm(
                 ActionFormProps::builder()
-                    .action(login)
+                    .action(verify_totp)
                     .children(ToChildren::to_children(move || {
                         (
                             div().class("mb-3 col-xs-1 col-xl-2").child((
                                 {
                                     label()
                                         .class("form-label")
-                                        .r#for("ref1")
-                                        .child(t![i18n, username])
+                                        .r#for("ref3")
+                                        .child(t![i18n, totpCode])
                                 },
                                 {
                                     input()
                                         .r#type("text")
                                         .class("form-control")
-                                        .id("ref1")
-                                        .name("params[username]")
+                                        .id("ref3")
+                                        .name("params[code]")
+                                        .attr("autocomplete", "one-time-code")
+                                        .maxlength(RECOVERY_CODE_LENGTH as i64 + 1)
                                 },
                                 {
                                     div()
                                         .class("invalid-feedback")
-                                        .child(t!(i18n, usernameRequired))
+                                        .child(t!(i18n, totpCodeRequired))
                                 },
                             )),
                             {
-                                div().class("mb-3 col-xs-1 col-xl-2").child((
-                                    {
-                                        label()
-                                            .class("form-label")
-                                            .r#for("ref2")
-                                            .child(t![i18n, password])
-                                    },
-                                    {
-                                        input()
-                                            .r#type("password")
-                                            .class("form-control")
-                                            .id("ref2")
-                                            .name("params[password]")
-                                            .required(true)
-                                            .maxlength(PASSWORD_MAX_LENGTH as i64)
-                                    },
-                                    {
-                                        div()
-                                            .class("invalid-feedback")
-                                            .child(t!(i18n, passwordRequired))
-                                    },
-                                ))
+                                input()
+                                    .r#type("hidden")
+                                    .name("params[challenge]")
+                                    // needed for the input to be reactive
+                                    .prop("value", move || totp_challenge.get().unwrap_or_default())
                             },
                             {
                                 button()
                                     .r#type("submit")
                                     .class("btn btn-primary")
-                                    .child(t![i18n, login])
+                                    .child(t![i18n, verifyTotp])
+                            },
+                            {
+                                button()
+                                    .r#type("button")
+                                    .class("btn btn-link")
+                                    .on(ev::click, move |_| set_totp_challenge.set(None))
+                                    .child(t![i18n, backToLogin])
                             },
-                            { div().class("mt-2").child(move || message()) },
+                            { div().class("mt-2").child(message) },
                         )
                     }))
                     .build(),
@@ -241,25 +473,35 @@ pub fn Login(
                 event::capture(event::submit),
                 validated_on_client,
             ))
-        }))
+        },
+    ))
 }
 
-fn show_error(ev: &SubmitEvent, form: &HtmlFormElement, index: u32) {
+/// Marks the input in the `index`th child of the form as invalid and stops submitting.
+pub(crate) fn show_error(ev: &SubmitEvent, form: &HtmlFormElement, index: u32) {
     let input_div = form.children().get_with_index(index).unwrap();
     let input = input_div.children().get_with_index(1).unwrap();
     input.class_list().add_1("is-invalid").unwrap();
     ev.prevent_default();
 }
 
+/// Checks the credentials and starts a session.
+///
+/// If the account has 2FA enabled, no session is started yet. Instead, a `TotpChallenge`
+/// is returned, which has to be completed by `verify_totp`.
 #[server]
-pub async fn login(params: LoginCallParams) -> Result<ApiResponse<()>, ServerFnError> {
+pub async fn login(
+    params: LoginCallParams,
+) -> Result<ApiResponse<Option<TotpChallenge>>, ServerFnError> {
     use crate::api::error::return_early;
     use crate::api::error::ApiError;
-    use crate::api::jwt::{JwtClaim, JwtKeys};
+    use crate::server_utils::auth_backend::{AuthBackend, AuthOutcome, LoginAccount};
+    use crate::server_utils::configuration::{LoginLockoutSettings, TotpSettings};
+    use crate::server_utils::login_lockout::{record_failed_login, reset_failed_logins};
+    use crate::server_utils::token::generate_token;
     use actix_web::web::Data;
     use bcrypt::verify;
-    use jsonwebtoken::encode;
-    use jsonwebtoken::Header;
+    use chrono::{TimeDelta, Utc};
     use log::{log, Level};
     use sqlx::query;
     use sqlx::{Pool, Postgres};
@@ -280,6 +522,20 @@ pub async fn login(params: LoginCallParams) -> Result<ApiResponse<()>, ServerFnE
         }
         Some(dummy_hash) => dummy_hash,
     };
+    let lockout_settings = match use_context::<Data<LoginLockoutSettings>>() {
+        None => {
+            log!(Level::Warn, "No login lockout settings found in context");
+            return return_early(ApiError::UnexpectedError("Configuration Error".to_string()));
+        }
+        Some(lockout_settings) => lockout_settings,
+    };
+    let auth_backend = match use_context::<Data<dyn AuthBackend>>() {
+        None => {
+            log!(Level::Warn, "No auth backend found in context");
+            return return_early(ApiError::UnexpectedError("Configuration Error".to_string()));
+        }
+        Some(auth_backend) => auth_backend,
+    };
     let params = match params.validated() {
         Err(_) => {
             log!(Level::Warn, "Invalid login params");
@@ -289,7 +545,7 @@ pub async fn login(params: LoginCallParams) -> Result<ApiResponse<()>, ServerFnE
     };
     let account_row_result = query!(
         "\
-            SELECT pw_hash, id \
+            SELECT pw_hash, id, locked_until, totp_enabled_at, disabled_at \
             FROM account \
             WHERE username = $1 \
         ",
@@ -306,38 +562,138 @@ pub async fn login(params: LoginCallParams) -> Result<ApiResponse<()>, ServerFnE
                 return_early(ApiError::InvalidCredentials)
             }
             Some(account_row_record) => {
-                let verify_result = verify(&params.password, &account_row_record.pw_hash);
-                let verified = verify_result.unwrap_or_else(|e| {
-                    log!(Level::Warn, "Error verifying password: {}", e);
-                    false
-                });
-                if !verified {
-                    return return_early(ApiError::InvalidCredentials);
+                // don't even verify the password of a locked account
+                if let Some(locked_until) = account_row_record.locked_until {
+                    let locked_until = locked_until.and_utc().timestamp();
+                    if locked_until > Utc::now().timestamp() {
+                        log!(Level::Info, "Login to locked account: {}", params.username);
+                        return return_early(ApiError::AccountLocked(locked_until));
+                    }
+                }
+                let login_account = LoginAccount {
+                    username: params.username.clone(),
+                    pw_hash: account_row_record.pw_hash,
+                };
+                let outcome = match auth_backend
+                    .authenticate(&login_account, &params.password)
+                    .await
+                {
+                    Err(err) => {
+                        log!(Level::Error, "Error checking password: {}", err);
+                        return return_early(ApiError::UnexpectedError(
+                            "Authentication Error".to_string(),
+                        ));
+                    }
+                    Ok(outcome) => outcome,
+                };
+                let AuthOutcome::Accepted { name } = outcome else {
+                    return match record_failed_login(
+                        &db_pool,
+                        account_row_record.id,
+                        &lockout_settings,
+                    )
+                    .await
+                    {
+                        Ok(None) => return_early(ApiError::InvalidCredentials),
+                        Ok(Some(locked_until)) => {
+                            log!(Level::Warn, "Locked account: {}", params.username);
+                            return_early(ApiError::AccountLocked(
+                                locked_until.and_utc().timestamp(),
+                            ))
+                        }
+                        Err(err) => return_early(ApiError::DbError(format!(
+                            "Error recording failed login: {}",
+                            err
+                        ))),
+                    };
+                };
+                if let Err(err) = reset_failed_logins(&db_pool, account_row_record.id).await {
+                    return return_early(ApiError::DbError(format!(
+                        "Error resetting failed logins: {}",
+                        err
+                    )));
+                }
+                // only tell who knows the password that the account is disabled
+                if account_row_record.disabled_at.is_some() {
+                    log!(
+                        Level::Info,
+                        "Login to disabled account: {}",
+                        params.username
+                    );
+                    return return_early(ApiError::AccountDisabled);
                 }
-                let session_row = query!(
-                    "INSERT INTO session (account_id) VALUES ($1) RETURNING id, expires_at",
-                    account_row_record.id
+                // external backends are the source of truth for the name
+                if let Some(name) = name {
+                    let name = name
+                        .chars()
+                        .take(NAME_MAX_LENGTH as usize)
+                        .collect::<String>();
+                    if let Err(err) = query!(
+                        "UPDATE account SET name = $1 WHERE id = $2 AND name <> $1",
+                        name,
+                        account_row_record.id
+                    )
+                    .execute(&**db_pool)
+                    .await
+                    {
+                        log!(Level::Warn, "Error syncing name: {}", err);
+                    }
+                }
+                if account_row_record.totp_enabled_at.is_none() {
+                    return start_session(&db_pool, account_row_record.id, &params.username, None)
+                        .await;
+                }
+
+                // the password was right, but a second factor is needed
+                let totp_settings = match use_context::<Data<TotpSettings>>() {
+                    None => {
+                        log!(Level::Warn, "No TOTP settings found in context");
+                        return return_early(ApiError::UnexpectedError(
+                            "Configuration Error".to_string(),
+                        ));
+                    }
+                    Some(totp_settings) => totp_settings,
+                };
+                let (challenge, challenge_hash) = match generate_token() {
+                    Err(err) => {
+                        log!(Level::Error, "Error generating login challenge: {}", err);
+                        return return_early(ApiError::UnexpectedError("Random Error".to_string()));
+                    }
+                    Ok(challenge) => challenge,
+                };
+                let expires_at = Utc::now().naive_utc()
+                    + TimeDelta::minutes(totp_settings.challenge_expiry_mins as i64);
+                let insert_result = query!(
+                    "\
+                        INSERT INTO login_challenge (account_id, token_hash, expires_at) \
+                        VALUES ($1, $2, $3)\
+                    ",
+                    account_row_record.id,
+                    challenge_hash,
+                    expires_at
                 )
-                .fetch_one(&**db_pool)
+                .execute(&**db_pool)
                 .await;
-                match session_row {
-                    Ok(session_row_record) => {
-                        let jwt_keys =
-                            use_context::<Data<JwtKeys>>().expect("No JWT keys from server");
-                        let claim = JwtClaim::new(session_row_record.id);
-                        let token = encode(&Header::default(), &claim, &jwt_keys.encode_key)
-                            .expect("JWT encode failed");
-                        log!(Level::Info, "Logged in: {}", params.username);
+                match insert_result {
+                    Ok(_) => {
+                        log!(
+                            Level::Info,
+                            "Password verified, TOTP pending: {}",
+                            params.username
+                        );
                         Ok(ApiResponse {
                             error: None,
-                            expires_at: session_row_record.expires_at.and_utc().timestamp(),
-                            token,
-                            data: (),
+                            expires_at: 0,
+                            token: "".to_string(),
+                            data: Some(TotpChallenge {
+                                challenge,
+                                expires_at: expires_at.and_utc().timestamp(),
+                            }),
                         })
                     }
                     Err(err) => return_early(ApiError::DbError(format!(
-                        "Error inserting session: {}",
-                        err.to_string()
+                        "Error inserting login challenge: {}",
+                        err
                     ))),
                 }
             }
@@ -346,41 +702,402 @@ pub async fn login(params: LoginCallParams) -> Result<ApiResponse<()>, ServerFnE
     }
 }
 
-#[server(client = crate::client::AddAuthHeaderClient)]
-pub async fn get_user() -> Result<ApiResponse<User>, ServerFnError> {
-    use crate::model::language::Language;
-    use actix_web::web::Data;
-    use actix_web::HttpMessage;
-    use leptos_actix::extract;
+/// Starts a session for an authenticated account and returns its token.
+#[cfg(feature = "ssr")]
+pub(crate) async fn start_session<T: Default>(
+    db_pool: &sqlx::Pool<sqlx::Postgres>,
+    account_id: sqlx::types::Uuid,
+    username: &str,
+    data: T,
+) -> Result<ApiResponse<T>, ServerFnError> {
+    use crate::api::error::return_early;
+    use log::{log, Level};
     use sqlx::query;
-    use sqlx::types::Uuid;
-    use sqlx::{Pool, Postgres};
 
-    let req: actix_web::HttpRequest = extract().await?;
-    let account_id = req.extensions_mut().get::<Uuid>().unwrap().clone();
-    let token = req.extensions_mut().get::<String>().unwrap().to_string();
-    let expires_at = req.extensions_mut().get::<i64>().unwrap().clone();
-    let user_row_result = query!(
+    let (token, expires_at) = match insert_session(account_id, None).await {
+        Err(err) => return return_early(err),
+        Ok(session) => session,
+    };
+    // shown on the profile page, the login itself succeeds without it
+    if let Err(err) = query!(
         "\
-            SELECT name, preferred_language as \"preferred_language: Language\" \
-            FROM account \
-            WHERE id = $1 \
+            UPDATE account \
+            SET previous_login_at = last_login_at, last_login_at = current_timestamp \
+            WHERE id = $1\
         ",
         account_id
-    );
-    let user_row = user_row_result
-        .fetch_one(&**use_context::<Data<Pool<Postgres>>>().unwrap())
-        .await?;
+    )
+    .execute(db_pool)
+    .await
+    {
+        log!(Level::Warn, "Error updating login times: {}", err);
+    }
+    log!(Level::Info, "Logged in: {}", username);
 
     Ok(ApiResponse {
+        error: None,
         expires_at,
         token,
-        error: None,
-        data: {
-            User {
-                name: user_row.name,
-                preferred_language: user_row.preferred_language.to_string(),
+        data,
+    })
+}
+
+/// Inserts a session for `account_id` and returns its token and expiry timestamp.
+///
+/// `impersonator_id` is the admin who started the session for another account, if any.
+///
+/// If the transport uses cookies, the token is set as session cookie. Without bearer tokens,
+/// the returned token is empty, so it doesn't get into the session storage.
+#[cfg(feature = "ssr")]
+pub(crate) async fn insert_session(
+    account_id: sqlx::types::Uuid,
+    impersonator_id: Option<sqlx::types::Uuid>,
+) -> Result<(String, i64), ApiError> {
+    use crate::api::jwt::JwtKeys;
+    use crate::server_utils::configuration::CsrfSettings;
+    use crate::server_utils::csrf::csrf_cookie;
+    use crate::server_utils::session_cookie::{session_cookie, set_cookie};
+    use crate::server_utils::session_store::{NewSession, SessionStore};
+    use crate::server_utils::token::generate_token;
+    use actix_web::web::Data;
+    use leptos_actix::extract;
+    use log::{log, Level};
+
+    // helps the user to recognize the session on the sessions page
+    let req: actix_web::HttpRequest = match extract().await {
+        Err(err) => {
+            log!(Level::Error, "Couldn't extract request: {}", err);
+            return Err(ApiError::UnexpectedError("Request Error".to_string()));
+        }
+        Ok(req) => req,
+    };
+    let user_agent = req
+        .headers()
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|header_value| header_value.to_str().ok())
+        .map(|user_agent| {
+            user_agent
+                .chars()
+                .take(USER_AGENT_MAX_LENGTH)
+                .collect::<String>()
+        });
+    // every session gets its own CSRF token, the client sends it with its server fn calls
+    let (csrf_token, csrf_token_hash) = match generate_token() {
+        Err(err) => {
+            log!(Level::Error, "Couldn't generate CSRF token: {}", err);
+            return Err(ApiError::UnexpectedError("Random Error".to_string()));
+        }
+        Ok(token) => token,
+    };
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let session = session_store
+        .create(NewSession {
+            account_id,
+            user_agent,
+            impersonator_id,
+            csrf_token_hash,
+        })
+        .await;
+    match session {
+        Ok(session) => {
+            let jwt_keys = use_context::<Data<JwtKeys>>().expect("No JWT keys from server");
+            let expires_at = session.expires_at.and_utc().timestamp();
+            let token = match jwt_keys.encode_token(session.id, expires_at) {
+                Err(err) => {
+                    log!(Level::Error, "Couldn't encode JWT: {}", err);
+                    return Err(ApiError::UnexpectedError("JWT Error".to_string()));
+                }
+                Ok(token) => token,
+            };
+            let csrf_settings =
+                use_context::<Data<CsrfSettings>>().expect("No CSRF settings from server");
+            set_cookie(csrf_cookie(&csrf_token, &csrf_settings));
+            if jwt_keys.transport.uses_cookie() {
+                set_cookie(session_cookie(&token, expires_at));
             }
-        },
+            if jwt_keys.transport.uses_bearer() {
+                Ok((token, expires_at))
+            } else {
+                Ok(("".to_string(), expires_at))
+            }
+        }
+        Err(err) => Err(ApiError::DbError(err.to_string())),
+    }
+}
+
+/// Completes a login started by `login` with a TOTP or a recovery code and starts the session.
+///
+/// Wrong codes count as failed logins, so they may lock the account.
+#[server]
+pub async fn verify_totp(params: VerifyTotpCallParams) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::error::return_early;
+    use crate::server_utils::configuration::{LoginLockoutSettings, TotpSettings};
+    use crate::server_utils::login_lockout::{record_failed_login, reset_failed_logins};
+    use crate::server_utils::token::hash_token;
+    use crate::server_utils::totp::{find_matching_step, new_totp, normalize_code};
+    use actix_web::web::Data;
+    use chrono::Utc;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let lockout_settings = match use_context::<Data<LoginLockoutSettings>>() {
+        None => {
+            log!(Level::Warn, "No login lockout settings found in context");
+            return return_early(ApiError::UnexpectedError("Configuration Error".to_string()));
+        }
+        Some(lockout_settings) => lockout_settings,
+    };
+    let totp_settings = match use_context::<Data<TotpSettings>>() {
+        None => {
+            log!(Level::Warn, "No TOTP settings found in context");
+            return return_early(ApiError::UnexpectedError("Configuration Error".to_string()));
+        }
+        Some(totp_settings) => totp_settings,
+    };
+    let params = match params.validated() {
+        Err(_) => {
+            log!(Level::Warn, "Invalid verify TOTP params");
+            return return_early(ApiError::InvalidTotpCode);
+        }
+        Ok(params) => params,
+    };
+    let code = normalize_code(&params.code);
+    let now = Utc::now().naive_utc();
+
+    let challenge_row = match query!(
+        "\
+            SELECT account.id, account.username, account.locked_until, account.totp_secret, \
+                account.totp_last_used_step \
+            FROM login_challenge \
+            JOIN account ON account.id = login_challenge.account_id \
+            WHERE login_challenge.token_hash = $1 AND login_challenge.expires_at > $2 \
+                AND account.totp_enabled_at IS NOT NULL AND account.disabled_at IS NULL\
+        ",
+        hash_token(&params.challenge),
+        now
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting login challenge: {}",
+                err
+            )))
+        }
+        Ok(None) => return return_early(ApiError::Expired),
+        Ok(Some(row)) => row,
+    };
+    if let Some(locked_until) = challenge_row.locked_until {
+        let locked_until = locked_until.and_utc().timestamp();
+        if locked_until > Utc::now().timestamp() {
+            return return_early(ApiError::AccountLocked(locked_until));
+        }
+    }
+
+    let verify_result = if code.len() == TOTP_CODE_LENGTH {
+        let totp = match new_totp(
+            challenge_row.totp_secret.as_deref().unwrap_or_default(),
+            &totp_settings.issuer,
+            &challenge_row.username,
+        ) {
+            Err(err) => {
+                log!(
+                    Level::Error,
+                    "Invalid TOTP of {}: {}",
+                    challenge_row.username,
+                    err
+                );
+                return return_early(ApiError::UnexpectedError("TOTP Error".to_string()));
+            }
+            Ok(totp) => totp,
+        };
+        match find_matching_step(&totp, &code, challenge_row.totp_last_used_step) {
+            None => Ok(false),
+            // the condition keeps the code from being used twice by concurrent requests
+            Some(step) => query!(
+                "\
+                    UPDATE account \
+                    SET totp_last_used_step = $2 \
+                    WHERE id = $1 AND (totp_last_used_step IS NULL OR totp_last_used_step < $2)\
+                ",
+                challenge_row.id,
+                step
+            )
+            .execute(&**db_pool)
+            .await
+            .map(|result| result.rows_affected() == 1),
+        }
+    } else {
+        query!(
+            "\
+                UPDATE totp_recovery_code \
+                SET used_at = $3 \
+                WHERE account_id = $1 AND code_hash = $2 AND used_at IS NULL\
+            ",
+            challenge_row.id,
+            hash_token(&code),
+            now
+        )
+        .execute(&**db_pool)
+        .await
+        .map(|result| {
+            if result.rows_affected() > 0 {
+                log!(
+                    Level::Info,
+                    "Used recovery code: {}",
+                    challenge_row.username
+                );
+            }
+            result.rows_affected() > 0
+        })
+    };
+    let verified = match verify_result {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!("Error verifying TOTP: {}", err)))
+        }
+        Ok(verified) => verified,
+    };
+    if !verified {
+        return match record_failed_login(&db_pool, challenge_row.id, &lockout_settings).await {
+            Ok(None) => return_early(ApiError::InvalidTotpCode),
+            Ok(Some(locked_until)) => {
+                log!(Level::Warn, "Locked account: {}", challenge_row.username);
+                return_early(ApiError::AccountLocked(locked_until.and_utc().timestamp()))
+            }
+            Err(err) => return_early(ApiError::DbError(format!(
+                "Error recording failed login: {}",
+                err
+            ))),
+        };
+    }
+
+    // a challenge can only be completed once
+    if let Err(err) = query!(
+        "\
+            DELETE FROM login_challenge \
+            WHERE account_id = $1\
+        ",
+        challenge_row.id
+    )
+    .execute(&**db_pool)
+    .await
+    {
+        return return_early(ApiError::DbError(format!(
+            "Error deleting login challenges: {}",
+            err
+        )));
+    }
+    if let Err(err) = reset_failed_logins(&db_pool, challenge_row.id).await {
+        return return_early(ApiError::DbError(format!(
+            "Error resetting failed logins: {}",
+            err
+        )));
+    }
+
+    start_session(&db_pool, challenge_row.id, &challenge_row.username, ()).await
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn get_user() -> Result<ApiResponse<User>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use actix_web::web::Data;
+    use sqlx::{Pool, Postgres};
+
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    let user = load_user(&use_context::<Data<Pool<Postgres>>>().unwrap(), &auth).await?;
+
+    Ok(auth.response(user))
+}
+
+/// Loads the user of an authorised request, e.g., for `get_user` or the server-side rendering.
+#[cfg(feature = "ssr")]
+pub(crate) async fn load_user(
+    db_pool: &sqlx::Pool<sqlx::Postgres>,
+    auth: &crate::api::auth_context::AuthContext,
+) -> Result<User, sqlx::Error> {
+    use crate::model::language::Language;
+    use crate::model::role::Role;
+    use sqlx::query;
+
+    let user_row = query!(
+        "\
+            SELECT account.name, \
+                account.preferred_language as \"preferred_language: Language\", \
+                account.role as \"role: Role\", impersonator.name as \"impersonator?\" \
+            FROM account \
+            LEFT JOIN account impersonator ON impersonator.id = $2 \
+            WHERE account.id = $1 \
+        ",
+        auth.account_id,
+        auth.impersonator_id
+    )
+    .fetch_one(db_pool)
+    .await?;
+
+    Ok(User {
+        name: user_row.name,
+        preferred_language: user_row.preferred_language.to_string(),
+        role: user_row.role,
+        impersonator: user_row.impersonator,
     })
 }
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn logout() -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::api::error::ApiError;
+    use crate::server_utils::csrf::csrf_removal_cookie;
+    use crate::server_utils::session_cookie::{removal_cookie, set_cookie};
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use log::{log, Level};
+
+    let session_store = match use_context::<Data<dyn SessionStore>>() {
+        None => {
+            log!(Level::Warn, "No session store found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(session_store) => session_store,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    // API tokens aren't ended by logging out, but revoked on the token page
+    if let Err(err) = auth.require_session() {
+        return return_early(err);
+    }
+
+    let revoke_result = session_store.revoke(auth.session_id, auth.account_id).await;
+
+    match revoke_result {
+        Ok(_) => {
+            log!(Level::Info, "Logged out: {}", auth.account_id);
+            // also with bearer tokens, a cookie of a former configuration is removed
+            set_cookie(removal_cookie());
+            set_cookie(csrf_removal_cookie());
+            Ok(ApiResponse {
+                error: None,
+                expires_at: 0,
+                token: "".to_string(),
+                data: (),
+            })
+        }
+        Err(err) => return_early(ApiError::DbError(err.to_string())),
+    }
+}
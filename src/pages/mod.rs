@@ -1,5 +1,15 @@
+pub mod admin_users;
+pub mod api_tokens;
 pub mod home_page;
 pub mod not_found;
 pub mod privacy;
+pub mod profile;
 pub mod imprint;
-pub mod login;
\ No newline at end of file
+pub mod change_password;
+pub mod forbidden;
+pub mod forgot_password;
+pub mod login;
+pub mod oidc_login;
+pub mod reset_password;
+pub mod sessions;
+pub mod two_factor;
\ No newline at end of file
@@ -0,0 +1,520 @@
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::model::user::User;
+use crate::pages::login::complete_login;
+use crate::utils::{get_lang, reload_at};
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use leptos::{component, server, IntoView};
+use leptos_router::hooks::{use_navigate, use_query_map};
+
+/// Logins at the provider have to come back within this url length.
+#[cfg(feature = "ssr")]
+const ORIG_URL_MAX_LENGTH: usize = 255;
+
+/// The button on the login page to log in at the OpenID Connect provider, if one is configured.
+pub fn oidc_login_button(orig_url: String) -> impl IntoView {
+    let i18n = use_i18n();
+    let oidc_enabled = LocalResource::new(get_oidc_enabled);
+    let (error, set_error) = signal(None::<String>);
+
+    let start_login = move |_| {
+        let orig_url = orig_url.clone();
+        spawn_local(async move {
+            match start_oidc_login(orig_url).await {
+                Ok(response) => match response.error {
+                    None => reload_at(&response.data),
+                    Some(err) => set_error.set(Some(err.to_string())),
+                },
+                Err(err) => set_error.set(Some(err.to_string())),
+            }
+        })
+    };
+
+    move || match oidc_enabled.get() {
+        Some(Ok(true)) => div()
+            .class("mt-3")
+            .child((
+                {
+                    button()
+                        .r#type("button")
+                        .class("btn btn-outline-primary")
+                        .on(leptos::ev::click, start_login.clone())
+                        .child(t![i18n, singleSignOn])
+                },
+                {
+                    move || {
+                        error.get().map(|err| {
+                            div().class("alert alert-danger mt-2").child(t!(
+                                i18n,
+                                serverError,
+                                error = err
+                            ))
+                        })
+                    }
+                },
+            ))
+            .into_any(),
+        _ => div().hidden(true).into_any(),
+    }
+}
+
+/// The redirect target of the provider, which completes the login with the returned code.
+#[component]
+pub fn OidcCallback(
+    set_user: WriteSignal<Option<User>>,
+    lang_setter: WriteSignal<String>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let lang = get_lang();
+    let navigate = use_navigate();
+    let query = use_query_map().get_untracked();
+    let code = query.get("code").unwrap_or_default();
+    let state = query.get("state").unwrap_or_default();
+    let oidc_login = LocalResource::new(move || complete_oidc_login(code.clone(), state.clone()));
+
+    Effect::new(move || {
+        if let Some(Ok(response)) = oidc_login.get() {
+            if response.error.is_none() {
+                complete_login(
+                    response.token,
+                    response.expires_at,
+                    response.data,
+                    set_user,
+                    lang,
+                    lang_setter,
+                    navigate.clone(),
+                );
+            }
+        }
+    });
+
+    let message = move || match oidc_login.get() {
+        None => div()
+            .class("text-center")
+            .child(
+                div()
+                    .class("spinner-border")
+                    .role("status")
+                    .child(span().class("visually-hidden").child(t!(i18n, loading))),
+            )
+            .into_any(),
+        Some(Ok(response)) => match response.error {
+            None => div()
+                .class("alert alert-success")
+                .child(t!(i18n, redirecting))
+                .into_any(),
+            Some(err) => {
+                let error_message = match err {
+                    ApiError::AccountDisabled => t!(i18n, accountDisabled).into_any(),
+                    _ => t!(i18n, singleSignOnFailed).into_any(),
+                };
+                div()
+                    .class("alert alert-danger")
+                    .child(error_message)
+                    .into_any()
+            }
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+    };
+
+    div().class("container").child(
+        ({ h1().child(t![i18n, login]) }, { div().child(message) }, {
+            a().href("/login").child(t![i18n, backToLogin])
+        }),
+    )
+}
+
+#[server]
+pub async fn get_oidc_enabled() -> Result<bool, ServerFnError> {
+    use crate::server_utils::configuration::OidcSettings;
+    use actix_web::web::Data;
+
+    let oidc_settings = use_context::<Data<Option<OidcSettings>>>()
+        .ok_or_else(|| ServerFnError::new("No OIDC settings found in context"))?;
+
+    Ok(oidc_settings.is_some())
+}
+
+/// Starts a login at the provider and returns the url to send the browser to.
+///
+/// The state, the PKCE code verifier and the nonce are kept until the provider redirects back
+/// to `complete_oidc_login`. The state is also set as cookie, so only this browser can complete
+/// the login.
+#[server]
+pub async fn start_oidc_login(orig_url: String) -> Result<ApiResponse<String>, ServerFnError> {
+    use crate::api::error::return_early;
+    use crate::server_utils::configuration::OidcSettings;
+    use crate::server_utils::oidc::{authorization_url, discover, oidc_state_cookie};
+    use crate::server_utils::session_cookie::set_cookie;
+    use crate::server_utils::token::generate_token;
+    use actix_web::web::Data;
+    use chrono::{TimeDelta, Utc};
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let oidc_settings = match use_context::<Data<Option<OidcSettings>>>()
+        .and_then(|oidc_settings| oidc_settings.get_ref().clone())
+    {
+        None => {
+            log!(Level::Warn, "OIDC login without OIDC settings");
+            return return_early(ApiError::OidcLoginFailed);
+        }
+        Some(oidc_settings) => oidc_settings,
+    };
+    // validated again on the client after the login, but it mustn't overflow the column
+    let orig_url = if orig_url.contains("//") || orig_url.len() > ORIG_URL_MAX_LENGTH {
+        "/".to_string()
+    } else {
+        orig_url
+    };
+    let tokens = generate_token().and_then(|(state, state_hash)| {
+        let (code_verifier, _) = generate_token()?;
+        let (nonce, _) = generate_token()?;
+        Ok((state, state_hash, code_verifier, nonce))
+    });
+    let (state, state_hash, code_verifier, nonce) = match tokens {
+        Err(err) => {
+            log!(Level::Error, "Error generating OIDC login tokens: {}", err);
+            return return_early(ApiError::UnexpectedError("Random Error".to_string()));
+        }
+        Ok(tokens) => tokens,
+    };
+    let metadata = match discover(&oidc_settings).await {
+        Err(err) => {
+            log!(Level::Error, "Error discovering OIDC provider: {}", err);
+            return return_early(ApiError::OidcLoginFailed);
+        }
+        Ok(metadata) => metadata,
+    };
+    let url = match authorization_url(&metadata, &oidc_settings, &state, &code_verifier, &nonce) {
+        Err(err) => {
+            log!(
+                Level::Error,
+                "Error building OIDC authorization url: {}",
+                err
+            );
+            return return_early(ApiError::OidcLoginFailed);
+        }
+        Ok(url) => url,
+    };
+
+    let expires_at =
+        Utc::now().naive_utc() + TimeDelta::minutes(oidc_settings.login_expiry_mins as i64);
+    let insert_result = query!(
+        "\
+            INSERT INTO oidc_login (state_hash, code_verifier, nonce, orig_url, expires_at) \
+            VALUES ($1, $2, $3, $4, $5)\
+        ",
+        state_hash,
+        code_verifier,
+        nonce,
+        orig_url,
+        expires_at
+    )
+    .execute(&**db_pool)
+    .await;
+
+    match insert_result {
+        Ok(_) => {
+            set_cookie(oidc_state_cookie(&state, &oidc_settings));
+            Ok(ApiResponse {
+                error: None,
+                expires_at: 0,
+                token: "".to_string(),
+                data: url,
+            })
+        }
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error inserting OIDC login: {}",
+            err
+        ))),
+    }
+}
+
+/// Completes a login at the provider with the code of its redirect and starts a session.
+///
+/// The account is found by the provider's subject, linked by the configured claim or created,
+/// see `OidcSettings`. The state has to match the cookie set by `start_oidc_login`. Returns the
+/// url the login was started from.
+#[server]
+pub async fn complete_oidc_login(
+    code: String,
+    state: String,
+) -> Result<ApiResponse<String>, ServerFnError> {
+    use crate::api::error::return_early;
+    use crate::pages::login::{
+        length_validated, start_session, NAME_MAX_LENGTH, USERNAME_MAX_LENGTH,
+    };
+    use crate::server_utils::configuration::OidcSettings;
+    use crate::server_utils::oidc::{
+        discover, exchange_code, oidc_state_removal_cookie, OIDC_STATE_COOKIE,
+    };
+    use crate::server_utils::session_cookie::set_cookie;
+    use crate::server_utils::token::{generate_token, hash_token};
+    use actix_web::web::Data;
+    use bcrypt::{hash, DEFAULT_COST};
+    use chrono::Utc;
+    use leptos_actix::extract;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    const EMAIL_MAX_LENGTH: usize = 254;
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let oidc_settings = match use_context::<Data<Option<OidcSettings>>>()
+        .and_then(|oidc_settings| oidc_settings.get_ref().clone())
+    {
+        None => {
+            log!(Level::Warn, "OIDC callback without OIDC settings");
+            return return_early(ApiError::OidcLoginFailed);
+        }
+        Some(oidc_settings) => oidc_settings,
+    };
+    // the login has to be completed by the browser which started it
+    let req: actix_web::HttpRequest = match extract().await {
+        Err(err) => {
+            log!(Level::Error, "Couldn't extract request: {}", err);
+            return return_early(ApiError::UnexpectedError("Request Error".to_string()));
+        }
+        Ok(req) => req,
+    };
+    set_cookie(oidc_state_removal_cookie());
+    let state_cookie_hash = req
+        .cookie(OIDC_STATE_COOKIE)
+        .map(|cookie| hash_token(cookie.value()));
+    if state_cookie_hash != Some(hash_token(&state)) {
+        log!(Level::Warn, "OIDC callback with state of another browser");
+        return return_early(ApiError::OidcLoginFailed);
+    }
+
+    // a state is only usable once
+    let login_row = match query!(
+        "\
+            DELETE FROM oidc_login \
+            WHERE state_hash = $1 \
+            RETURNING code_verifier, nonce, orig_url, expires_at\
+        ",
+        hash_token(&state)
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error deleting OIDC login: {}",
+                err
+            )))
+        }
+        Ok(None) => {
+            log!(Level::Info, "OIDC callback with unknown state");
+            return return_early(ApiError::OidcLoginFailed);
+        }
+        Ok(Some(row)) => row,
+    };
+    if login_row.expires_at < Utc::now().naive_utc() {
+        log!(Level::Info, "OIDC callback for expired login");
+        return return_early(ApiError::OidcLoginFailed);
+    }
+
+    let metadata = match discover(&oidc_settings).await {
+        Err(err) => {
+            log!(Level::Error, "Error discovering OIDC provider: {}", err);
+            return return_early(ApiError::OidcLoginFailed);
+        }
+        Ok(metadata) => metadata,
+    };
+    let claims = match exchange_code(
+        &metadata,
+        &oidc_settings,
+        &code,
+        &login_row.code_verifier,
+        &login_row.nonce,
+    )
+    .await
+    {
+        Err(err) => {
+            log!(Level::Warn, "Error exchanging OIDC code: {}", err);
+            return return_early(ApiError::OidcLoginFailed);
+        }
+        Ok(claims) => claims,
+    };
+
+    let account_row = match query!(
+        "SELECT id, username, disabled_at FROM account WHERE oidc_subject = $1",
+        claims.subject
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting OIDC account: {}",
+                err
+            )))
+        }
+        Ok(row) => row.map(|row| (row.id, row.username, row.disabled_at)),
+    };
+    let account_row = match account_row {
+        Some(account_row) => account_row,
+        None => {
+            let username = match claims.get(&oidc_settings.link_claim) {
+                Some(username) if length_validated(&username, USERNAME_MAX_LENGTH) => username,
+                _ => {
+                    log!(
+                        Level::Warn,
+                        "OIDC subject {} without a valid {} claim",
+                        claims.subject,
+                        oidc_settings.link_claim
+                    );
+                    return return_early(ApiError::OidcLoginFailed);
+                }
+            };
+            // users may choose unverified claims at the provider, so they mustn't link
+            let linked_row = if !claims.is_verified(&oidc_settings.link_claim) {
+                log!(
+                    Level::Info,
+                    "Not linking OIDC subject {} by the unverified {} claim",
+                    claims.subject,
+                    oidc_settings.link_claim
+                );
+                None
+            } else {
+                // accounts already linked to another subject and admins aren't taken over
+                match query!(
+                    "\
+                        UPDATE account \
+                        SET oidc_subject = $1 \
+                        WHERE username = $2 AND oidc_subject IS NULL AND role < 'admin' \
+                        RETURNING id, username, disabled_at\
+                    ",
+                    claims.subject,
+                    username
+                )
+                .fetch_optional(&**db_pool)
+                .await
+                {
+                    Err(err) => {
+                        return return_early(ApiError::DbError(format!(
+                            "Error linking OIDC account: {}",
+                            err
+                        )))
+                    }
+                    Ok(row) => row,
+                }
+            };
+            match linked_row {
+                Some(row) => {
+                    log!(
+                        Level::Info,
+                        "Account {} linked to OIDC subject {}",
+                        row.username,
+                        claims.subject
+                    );
+                    (row.id, row.username, row.disabled_at)
+                }
+                None if oidc_settings.provision_accounts => {
+                    let name = claims
+                        .get("name")
+                        .filter(|name| !name.is_empty())
+                        .unwrap_or_else(|| username.clone())
+                        .chars()
+                        .take(NAME_MAX_LENGTH as usize)
+                        .collect::<String>();
+                    let email = claims
+                        .get("email")
+                        .filter(|email| email.len() <= EMAIL_MAX_LENGTH && email.contains('@'));
+                    // there is no password to log in with, until an admin resets it
+                    let pw_hash = match generate_token().map_err(|err| err.to_string()).and_then(
+                        |(password, _)| hash(password, DEFAULT_COST).map_err(|err| err.to_string()),
+                    ) {
+                        Err(err) => {
+                            log!(Level::Error, "Error hashing random password: {}", err);
+                            return return_early(ApiError::UnexpectedError(
+                                "Hashing Error".to_string(),
+                            ));
+                        }
+                        Ok(pw_hash) => pw_hash,
+                    };
+                    match query!(
+                        "\
+                            INSERT INTO account (username, pw_hash, name, email, oidc_subject) \
+                            VALUES ($1, $2, $3, $4, $5) \
+                            RETURNING id\
+                        ",
+                        username,
+                        pw_hash,
+                        name,
+                        email,
+                        claims.subject
+                    )
+                    .fetch_one(&**db_pool)
+                    .await
+                    {
+                        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => {
+                            log!(
+                                Level::Warn,
+                                "OIDC account {} exists, but is linked to another subject",
+                                username
+                            );
+                            return return_early(ApiError::OidcLoginFailed);
+                        }
+                        Err(err) => {
+                            return return_early(ApiError::DbError(format!(
+                                "Error inserting OIDC account: {}",
+                                err
+                            )))
+                        }
+                        Ok(row) => {
+                            log!(
+                                Level::Info,
+                                "Account {} ({}) created for OIDC subject {}",
+                                username,
+                                row.id,
+                                claims.subject
+                            );
+                            (row.id, username, None)
+                        }
+                    }
+                }
+                None => {
+                    log!(
+                        Level::Info,
+                        "No account for OIDC user {} ({})",
+                        username,
+                        claims.subject
+                    );
+                    return return_early(ApiError::OidcLoginFailed);
+                }
+            }
+        }
+    };
+
+    let (account_id, username, disabled_at) = account_row;
+    if disabled_at.is_some() {
+        log!(Level::Info, "OIDC login to disabled account: {}", username);
+        return return_early(ApiError::AccountDisabled);
+    }
+    // the provider is responsible for the second factor
+    start_session(&db_pool, account_id, &username, login_row.orig_url).await
+}
@@ -0,0 +1,361 @@
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::model::language::Language;
+use crate::model::profile::Profile;
+use crate::model::user::User;
+use crate::pages::api_tokens::ApiTokens;
+use crate::pages::login::{length_validated, show_error, NAME_MAX_LENGTH};
+use crate::utils::{
+    csrf_field, format_timestamp, get_lang, set_login_data_to_session_storage, set_user_lang,
+};
+use leptos::form::ActionForm;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::tachys::html::event;
+use leptos::{component, server, IntoView};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlFormElement, SubmitEvent};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProfileCallParams {
+    name: String,
+    preferred_language: Language,
+}
+
+impl ProfileCallParams {
+    fn validated(&self) -> Result<ProfileCallParams, ()> {
+        if !length_validated(&self.name, NAME_MAX_LENGTH) {
+            return Err(());
+        };
+
+        Ok(self.clone())
+    }
+}
+
+#[component]
+pub fn ProfilePage(
+    set_user: WriteSignal<Option<User>>,
+    lang_setter: WriteSignal<String>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let lang = get_lang();
+    let profile = LocalResource::new(get_profile);
+    let update_profile = ServerAction::<UpdateProfile>::new();
+
+    Effect::new(move || {
+        if let Some(Ok(response)) = profile.get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+    // the navbar shows the new name and the page switches to the new language
+    Effect::new(move || {
+        if let Some(Ok(response)) = update_profile.value().get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                set_user_lang(&response.data.preferred_language, lang, lang_setter);
+                set_user.set(Some(response.data));
+            }
+        }
+    });
+
+    let message = move || {
+        let value = update_profile.value().get();
+        let pending = update_profile.pending().get();
+
+        if pending {
+            return div()
+                .class("text-center")
+                .child(
+                    div()
+                        .class("spinner-border")
+                        .role("status")
+                        .child(span().class("visually-hidden").child(t!(i18n, loading))),
+                )
+                .into_any();
+        }
+
+        match value {
+            Some(Ok(response)) => match response.error {
+                None => div()
+                    .class("alert alert-success")
+                    .child(t!(i18n, profileSaved))
+                    .into_any(),
+                Some(err) => div()
+                    .class("alert alert-danger")
+                    .child(t!(i18n, serverError, error = err.to_string()))
+                    .into_any(),
+            },
+            Some(Err(err)) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+            None => div().hidden(true).into_any(),
+        }
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match UpdateProfile::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        if data.params.validated().is_err() {
+            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+            show_error(&ev, &form, 0);
+        }
+    };
+
+    let profile_view = move |profile: Profile| {
+        let name = profile.name.clone();
+        let preferred_language = profile.preferred_language.clone();
+        (
+            dl().class("row").child((
+                { dt().class("col-sm-3").child(t![i18n, username]) },
+                { dd().class("col-sm-9").child(profile.username) },
+                { dt().class("col-sm-3").child(t![i18n, memberSince]) },
+                {
+                    dd().class("col-sm-9")
+                        .child(format_timestamp(profile.created_at))
+                },
+                { dt().class("col-sm-3").child(t![i18n, previousLogin]) },
+                {
+                    dd().class("col-sm-9")
+                        .child(match profile.previous_login_at {
+                            Some(previous_login_at) => {
+                                format_timestamp(previous_login_at).into_any()
+                            }
+                            None => t![i18n, noPreviousLogin].into_any(),
+                        })
+                },
+            )),
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(update_profile)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref1")
+                                        .child(t![i18n, name])
+                                },
+                                {
+                                    input()
+                                        .r#type("text")
+                                        .class("form-control")
+                                        .id("ref1")
+                                        .name("params[name]")
+                                        .required(true)
+                                        .maxlength(NAME_MAX_LENGTH as i64)
+                                        .value(name.clone())
+                                },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, nameRequired))
+                                },
+                            )),
+                            // the option values are the serialized enum variants
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref2")
+                                        .child(t![i18n, preferred])
+                                },
+                                {
+                                    select()
+                                        .class("form-select")
+                                        .id("ref2")
+                                        .name("params[preferred_language]")
+                                        .child((
+                                            {
+                                                option()
+                                                    .value("En")
+                                                    .selected(preferred_language == Language::En)
+                                                    .child(t![i18n, english])
+                                            },
+                                            {
+                                                option()
+                                                    .value("De")
+                                                    .selected(preferred_language == Language::De)
+                                                    .child(t![i18n, german])
+                                            },
+                                        ))
+                                },
+                            )),
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-primary")
+                                    .child(t![i18n, saveProfile])
+                            },
+                            { div().class("mt-2").child(message) },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            )),
+        )
+    };
+
+    let content = move || match profile.get() {
+        None => div()
+            .class("text-center")
+            .child(
+                div()
+                    .class("spinner-border")
+                    .role("status")
+                    .child(span().class("visually-hidden").child(t!(i18n, loading))),
+            )
+            .into_any(),
+        Some(Ok(response)) => match response.error {
+            None => div().child(profile_view(response.data)).into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, profile]) },
+        { div().child(content) },
+        { ApiTokens() },
+    ))
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn get_profile() -> Result<ApiResponse<Profile>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+
+    let account_row = match query!(
+        "\
+            SELECT username, name, preferred_language as \"preferred_language: Language\", \
+                created_at, previous_login_at \
+            FROM account \
+            WHERE id = $1\
+        ",
+        auth.account_id
+    )
+    .fetch_one(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting profile: {}",
+                err
+            )))
+        }
+        Ok(row) => row,
+    };
+
+    Ok(auth.response(Profile {
+        username: account_row.username,
+        name: account_row.name,
+        preferred_language: account_row.preferred_language,
+        created_at: account_row.created_at.and_utc().timestamp(),
+        previous_login_at: account_row
+            .previous_login_at
+            .map(|previous_login_at| previous_login_at.and_utc().timestamp()),
+    }))
+}
+
+/// Sets the display name and the preferred language of the logged-in user.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn update_profile(params: ProfileCallParams) -> Result<ApiResponse<User>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use crate::model::role::Role;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    let params = match params.validated() {
+        Err(_) => {
+            log!(Level::Warn, "Invalid profile params");
+            return return_early(ApiError::UnexpectedError(
+                "Invalid profile params".to_string(),
+            ));
+        }
+        Ok(params) => params,
+    };
+
+    let account_row = match query!(
+        "\
+            UPDATE account \
+            SET name = $1, preferred_language = $2 \
+            WHERE id = $3 \
+            RETURNING name, preferred_language as \"preferred_language: Language\", \
+                role as \"role: Role\", \
+                (SELECT name FROM account impersonator WHERE impersonator.id = $4) \
+                    as \"impersonator?\"\
+        ",
+        params.name,
+        params.preferred_language as Language,
+        auth.account_id,
+        auth.impersonator_id
+    )
+    .fetch_one(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error updating profile: {}",
+                err
+            )))
+        }
+        Ok(row) => row,
+    };
+
+    Ok(auth.response(User {
+        name: account_row.name,
+        preferred_language: account_row.preferred_language.to_string(),
+        role: account_row.role,
+        impersonator: account_row.impersonator,
+    }))
+}
@@ -0,0 +1,317 @@
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::pages::login::{length_validated, show_error, PASSWORD_MAX_LENGTH};
+use leptos::form::ActionForm;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::tachys::html::event;
+use leptos::{component, server, IntoView};
+use leptos_router::hooks::use_query_map;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlFormElement, SubmitEvent};
+
+/// The length of a hex encoded password reset token.
+const TOKEN_LENGTH: usize = 64;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResetPasswordCallParams {
+    token: String,
+    new_password: String,
+    new_password_repeated: String,
+}
+
+enum ResetPasswordCallParamsError {
+    InvalidToken,
+    InvalidNewPassword,
+    PasswordsDiffer,
+}
+
+impl ResetPasswordCallParams {
+    fn validated(&self) -> Result<ResetPasswordCallParams, ResetPasswordCallParamsError> {
+        if self.token.len() != TOKEN_LENGTH {
+            return Err(ResetPasswordCallParamsError::InvalidToken);
+        };
+        if !length_validated(&self.new_password, PASSWORD_MAX_LENGTH) {
+            return Err(ResetPasswordCallParamsError::InvalidNewPassword);
+        };
+        if self.new_password != self.new_password_repeated {
+            return Err(ResetPasswordCallParamsError::PasswordsDiffer);
+        };
+
+        Ok(self.clone())
+    }
+}
+
+#[component]
+pub fn ResetPassword() -> impl IntoView {
+    let i18n = use_i18n();
+    let confirm_password_reset = ServerAction::<ConfirmPasswordReset>::new();
+    let token = use_query_map()
+        .get_untracked()
+        .get("token")
+        .unwrap_or_default();
+
+    let invalid_link = move || {
+        div()
+            .class("alert alert-danger")
+            .child(({ t!(i18n, resetLinkInvalid) }, " ", {
+                a().href("/forgot-password").child(t!(i18n, forgotPassword))
+            }))
+    };
+
+    let message = move || {
+        let value = confirm_password_reset.value().get();
+        let pending = confirm_password_reset.pending().get();
+
+        if pending {
+            return div()
+                .class("text-center")
+                .child(
+                    div()
+                        .class("spinner-border")
+                        .role("status")
+                        .child(span().class("visually-hidden").child(t!(i18n, loading))),
+                )
+                .into_any();
+        }
+
+        match value {
+            Some(Ok(response)) => match response.error {
+                None => div()
+                    .class("alert alert-success")
+                    .child(({ t!(i18n, passwordResetDone) }, " ", {
+                        a().href("/login").child(t!(i18n, login))
+                    }))
+                    .into_any(),
+                Some(ApiError::InvalidResetToken) => invalid_link().into_any(),
+                Some(err) => div()
+                    .class("alert alert-danger")
+                    .child(t!(i18n, serverError, error = err.to_string()))
+                    .into_any(),
+            },
+            Some(Err(err)) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+            None => div().hidden(true).into_any(),
+        }
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match ConfirmPasswordReset::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+        match data.validated() {
+            Ok(_) => {}
+            // the link is broken, the server would reject it anyway
+            Err(ResetPasswordCallParamsError::InvalidToken) => ev.prevent_default(),
+            Err(ResetPasswordCallParamsError::InvalidNewPassword) => {
+                show_error(&ev, &form, 0);
+            }
+            Err(ResetPasswordCallParamsError::PasswordsDiffer) => {
+                show_error(&ev, &form, 1);
+            }
+        }
+    };
+
+    let password_input = move |id: &'static str, name: &'static str| {
+        input()
+            .r#type("password")
+            .class("form-control")
+            .id(id)
+            .name(name)
+            .required(true)
+            .maxlength(PASSWORD_MAX_LENGTH as i64)
+    };
+
+    let form = move || {
+        if token.len() != TOKEN_LENGTH {
+            return invalid_link().into_any();
+        }
+        let token = token.clone();
+        ActionForm(
+            ActionFormProps::builder()
+                .action(confirm_password_reset)
+                .children(ToChildren::to_children(move || {
+                    (
+                        div().class("mb-3 col-xs-1 col-xl-2").child((
+                            {
+                                label()
+                                    .class("form-label")
+                                    .r#for("ref1")
+                                    .child(t![i18n, newPassword])
+                            },
+                            { password_input("ref1", "params[new_password]") },
+                            {
+                                div()
+                                    .class("invalid-feedback")
+                                    .child(t!(i18n, passwordRequired))
+                            },
+                        )),
+                        div().class("mb-3 col-xs-1 col-xl-2").child((
+                            {
+                                label()
+                                    .class("form-label")
+                                    .r#for("ref2")
+                                    .child(t![i18n, newPasswordRepeated])
+                            },
+                            { password_input("ref2", "params[new_password_repeated]") },
+                            {
+                                div()
+                                    .class("invalid-feedback")
+                                    .child(t!(i18n, passwordsDiffer))
+                            },
+                        )),
+                        {
+                            input()
+                                .r#type("hidden")
+                                .name("params[token]")
+                                .value(token.clone())
+                        },
+                        {
+                            button()
+                                .r#type("submit")
+                                .class("btn btn-primary")
+                                .child(t![i18n, resetPassword])
+                        },
+                        { div().class("mt-2").child(message) },
+                    )
+                }))
+                .build(),
+        )
+        .attr("novalidate", "true")
+        .add_any_attr(event::on(
+            event::capture(event::submit),
+            validated_on_client,
+        ))
+        .into_any()
+    };
+
+    div()
+        .class("container")
+        .child(({ h1().child(t![i18n, resetPassword]) }, { form() }))
+}
+
+/// Sets a new password using the token from a reset link.
+///
+/// On success, the token is used up, all sessions of the account are revoked and
+/// a login lock is lifted.
+#[server]
+pub async fn confirm_password_reset(
+    params: ResetPasswordCallParams,
+) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::error::return_early;
+    use crate::server_utils::session_store::SessionStore;
+    use crate::server_utils::token::hash_token;
+    use actix_web::web::Data;
+    use bcrypt::{hash, DEFAULT_COST};
+    use chrono::Utc;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let params = match params.validated() {
+        Err(ResetPasswordCallParamsError::InvalidToken) => {
+            log!(Level::Warn, "Invalid password reset token");
+            return return_early(ApiError::InvalidResetToken);
+        }
+        Err(_) => {
+            log!(Level::Warn, "Invalid password reset params");
+            return return_early(ApiError::UnexpectedError(
+                "Invalid password params".to_string(),
+            ));
+        }
+        Ok(params) => params,
+    };
+    let pw_hash = match hash(&params.new_password, DEFAULT_COST) {
+        Err(err) => {
+            log!(Level::Error, "Error hashing password: {}", err);
+            return return_early(ApiError::UnexpectedError("Hashing Error".to_string()));
+        }
+        Ok(pw_hash) => pw_hash,
+    };
+    let token_hash = hash_token(&params.token);
+    let now = Utc::now().naive_utc();
+
+    let reset_result = async {
+        let mut tx = db_pool.begin().await?;
+        // consuming the token with a single update keeps it single-use for concurrent requests
+        let token_row = query!(
+            "\
+                UPDATE password_reset_token \
+                SET used_at = $2 \
+                WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2 \
+                RETURNING account_id\
+            ",
+            token_hash,
+            now
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let account_id = match token_row {
+            None => return Ok(None),
+            Some(row) => row.account_id,
+        };
+        query!(
+            "\
+                UPDATE account \
+                SET pw_hash = $1, failed_logins = 0, failed_login_window_start = NULL, \
+                    locked_until = NULL \
+                WHERE id = $2\
+            ",
+            pw_hash,
+            account_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Some(account_id))
+    }
+    .await;
+
+    match reset_result {
+        Ok(None) => {
+            log!(Level::Info, "Unknown, used or expired password reset token");
+            return_early(ApiError::InvalidResetToken)
+        }
+        Ok(Some(account_id)) => {
+            // whoever knew the old password mustn't stay logged in
+            let revoked = match session_store.revoke_all(account_id, None).await {
+                Err(err) => return return_early(ApiError::DbError(err.to_string())),
+                Ok(revoked) => revoked,
+            };
+            log!(
+                Level::Info,
+                "Reset password of account {} and revoked {} session(s)",
+                account_id,
+                revoked
+            );
+            Ok(ApiResponse {
+                expires_at: 0,
+                token: "".to_string(),
+                error: None,
+                data: (),
+            })
+        }
+        Err(err) => return_early(ApiError::DbError(format!(
+            "Error resetting password: {}",
+            err
+        ))),
+    }
+}
@@ -0,0 +1,216 @@
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::model::session::Session;
+use crate::utils::{format_timestamp, set_login_data_to_session_storage};
+use leptos::ev;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use leptos::{component, server, IntoView};
+
+#[component]
+pub fn Sessions() -> impl IntoView {
+    let i18n = use_i18n();
+    let sessions = LocalResource::new(get_sessions);
+
+    // keep the stored expiry in sync, as loading the sessions extends the current one
+    Effect::new(move || {
+        if let Some(Ok(response)) = sessions.get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+
+    // `None` revokes all sessions except the current one
+    let revoke = move |session_id: Option<String>| {
+        spawn_local(async move {
+            let _ = revoke_sessions(session_id).await;
+            sessions.refetch();
+        });
+    };
+
+    let session_list = move || match sessions.get() {
+        None => div()
+            .class("text-center")
+            .child(
+                div()
+                    .class("spinner-border")
+                    .role("status")
+                    .child(span().class("visually-hidden").child(t!(i18n, loading))),
+            )
+            .into_any(),
+        Some(Ok(response)) => match response.error {
+            None => session_table(response.data, revoke).into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, sessions]) },
+        { div().child(session_list) },
+        {
+            button()
+                .r#type("button")
+                .class("btn btn-outline-danger")
+                .on(ev::click, move |_| revoke(None))
+                .child(t![i18n, revokeOtherSessions])
+        },
+    ))
+}
+
+fn session_table(
+    sessions: Vec<Session>,
+    revoke: impl Fn(Option<String>) + Copy + 'static,
+) -> impl IntoView {
+    let i18n = use_i18n();
+    let rows = sessions
+        .into_iter()
+        .map(|session| {
+            let session_id = session.id.clone();
+            tr().child((
+                {
+                    td().child(match session.user_agent {
+                        None => t![i18n, unknownDevice].into_any(),
+                        Some(user_agent) => user_agent.into_any(),
+                    })
+                },
+                { td().child(format_timestamp(session.created_at)) },
+                { td().child(format_timestamp(session.last_used_at)) },
+                { td().child(format_timestamp(session.expires_at)) },
+                {
+                    td().child(if session.is_current {
+                        span()
+                            .class("badge text-bg-primary")
+                            .child(t![i18n, currentSession])
+                            .into_any()
+                    } else {
+                        button()
+                            .r#type("button")
+                            .class("btn btn-outline-danger btn-sm")
+                            .on(ev::click, move |_| revoke(Some(session_id.clone())))
+                            .child(t![i18n, revokeSession])
+                            .into_any()
+                    })
+                },
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    table().class("table").child((
+        {
+            thead().child(tr().child((
+                { th().attr("scope", "col").child(t![i18n, sessionDevice]) },
+                { th().attr("scope", "col").child(t![i18n, sessionCreated]) },
+                { th().attr("scope", "col").child(t![i18n, sessionLastUsed]) },
+                { th().attr("scope", "col").child(t![i18n, sessionExpires]) },
+                { th().attr("scope", "col") },
+            )))
+        },
+        { tbody().child(rows) },
+    ))
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn get_sessions() -> Result<ApiResponse<Vec<Session>>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+
+    let sessions = match session_store.list(auth.account_id).await {
+        Err(err) => return return_early(ApiError::DbError(err.to_string())),
+        Ok(sessions) => sessions,
+    };
+
+    Ok(auth.response(
+        sessions
+            .into_iter()
+            .map(|session| Session {
+                id: session.id.to_string(),
+                created_at: session.created_at.and_utc().timestamp(),
+                last_used_at: session.last_used_at.and_utc().timestamp(),
+                expires_at: session.expires_at.and_utc().timestamp(),
+                user_agent: session.user_agent,
+                is_current: session.id == auth.session_id,
+            })
+            .collect(),
+    ))
+}
+
+/// Revokes the session with the given id or, if `None` is given, all other sessions of the
+/// logged-in user. The current session is never revoked, use `logout` for that.
+///
+/// Returns the number of revoked sessions.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn revoke_sessions(
+    session_id: Option<String>,
+) -> Result<ApiResponse<u64>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::{return_early, ApiError};
+    use crate::server_utils::session_store::SessionStore;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::types::Uuid;
+    use std::str::FromStr;
+
+    let session_store =
+        use_context::<Data<dyn SessionStore>>().expect("No session store from server");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+
+    let revoke_result = match session_id {
+        Some(session_id) => {
+            let session_id = match Uuid::from_str(&session_id) {
+                Err(_) => {
+                    return return_early(ApiError::UnexpectedError(
+                        "Invalid session id".to_string(),
+                    ))
+                }
+                Ok(session_id) => session_id,
+            };
+            if session_id == auth.session_id {
+                Ok(0)
+            } else {
+                session_store
+                    .revoke(session_id, auth.account_id)
+                    .await
+                    .map(u64::from)
+            }
+        }
+        None => {
+            session_store
+                .revoke_all(auth.account_id, Some(auth.session_id))
+                .await
+        }
+    };
+
+    match revoke_result {
+        Ok(revoked) => {
+            log!(
+                Level::Info,
+                "Revoked {} session(s) of account {}",
+                revoked,
+                auth.account_id
+            );
+            Ok(auth.response(revoked))
+        }
+        Err(err) => return_early(ApiError::DbError(err.to_string())),
+    }
+}
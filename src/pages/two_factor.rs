@@ -0,0 +1,659 @@
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::i18n::*;
+use crate::model::two_factor::{TotpEnrollment, TotpStatus};
+use crate::pages::login::{length_validated, show_error, PASSWORD_MAX_LENGTH};
+use crate::utils::{csrf_field, set_login_data_to_session_storage};
+use leptos::ev;
+use leptos::form::ActionForm;
+use leptos::html::*;
+use leptos::prelude::*;
+use leptos::reactive::spawn_local;
+use leptos::tachys::html::event;
+use leptos::{component, server, IntoView};
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlFormElement, SubmitEvent};
+
+#[component]
+pub fn TwoFactor() -> impl IntoView {
+    let i18n = use_i18n();
+    let status = LocalResource::new(get_totp_status);
+    let confirm_totp_enrollment = ServerAction::<ConfirmTotpEnrollment>::new();
+    let disable_totp = ServerAction::<DisableTotp>::new();
+    // set while the user adds the account to an authenticator app
+    let (enrollment, set_enrollment) = signal(None::<TotpEnrollment>);
+
+    // keep the stored expiry in sync, as every call extends the current session
+    Effect::new(move || {
+        if let Some(Ok(response)) = status.get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+            }
+        }
+    });
+    Effect::new(move || {
+        if let Some(Ok(response)) = confirm_totp_enrollment.value().get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                set_enrollment.set(None);
+                status.refetch();
+            }
+        }
+    });
+    Effect::new(move || {
+        if let Some(Ok(response)) = disable_totp.value().get() {
+            if response.error.is_none() {
+                set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                status.refetch();
+            }
+        }
+    });
+
+    let on_enable = move |_| {
+        spawn_local(async move {
+            if let Ok(response) = start_totp_enrollment().await {
+                if response.error.is_none() {
+                    set_login_data_to_session_storage(response.token.as_str(), response.expires_at);
+                    set_enrollment.set(Some(response.data));
+                }
+            }
+        });
+    };
+
+    let recovery_codes = move || match confirm_totp_enrollment.value().get() {
+        Some(Ok(response)) if response.error.is_none() => div()
+            .class("alert alert-warning")
+            .child(({ p().child(t![i18n, recoveryCodesIntro]) }, {
+                ul().class("font-monospace").child(
+                    response
+                        .data
+                        .into_iter()
+                        .map(|code| li().child(code))
+                        .collect::<Vec<_>>(),
+                )
+            }))
+            .into_any(),
+        _ => div().hidden(true).into_any(),
+    };
+
+    let status_view = move || match status.get() {
+        None => div()
+            .class("text-center")
+            .child(
+                div()
+                    .class("spinner-border")
+                    .role("status")
+                    .child(span().class("visually-hidden").child(t!(i18n, loading))),
+            )
+            .into_any(),
+        Some(Ok(response)) => match response.error {
+            None if response.data.enabled => enabled_view(response.data, disable_totp).into_any(),
+            None => match enrollment.get() {
+                None => div()
+                    .child(({ p().child(t![i18n, twoFactorDisabled]) }, {
+                        button()
+                            .r#type("button")
+                            .class("btn btn-primary")
+                            .on(ev::click, on_enable)
+                            .child(t![i18n, enableTwoFactor])
+                    }))
+                    .into_any(),
+                Some(enrollment) => enrollment_view(enrollment, confirm_totp_enrollment).into_any(),
+            },
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+    };
+
+    div().class("container").child((
+        { h1().child(t![i18n, twoFactor]) },
+        { div().child(recovery_codes) },
+        { div().child(status_view) },
+    ))
+}
+
+fn enrollment_view(
+    enrollment: TotpEnrollment,
+    confirm_totp_enrollment: ServerAction<ConfirmTotpEnrollment>,
+) -> impl IntoView {
+    let i18n = use_i18n();
+
+    let message = move || match confirm_totp_enrollment.value().get() {
+        Some(Ok(response)) => match response.error {
+            None => div().hidden(true).into_any(),
+            Some(ApiError::InvalidTotpCode) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, invalidTotpCode))
+                .into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+        None => div().hidden(true).into_any(),
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match ConfirmTotpEnrollment::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        if !length_validated(&data.code, 8) {
+            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+            show_error(&ev, &form, 0);
+        }
+    };
+
+    div().child((
+        { p().child(t![i18n, scanQrCode]) },
+        {
+            img()
+                .src(format!("data:image/png;base64,{}", enrollment.qr_code))
+                .alt(enrollment.otpauth_uri.clone())
+                .width(200)
+        },
+        {
+            p().child(({ t![i18n, totpSecret] }, ": ", {
+                code().child(enrollment.secret)
+            }))
+        },
+        {
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(confirm_totp_enrollment)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref1")
+                                        .child(t![i18n, totpCode])
+                                },
+                                {
+                                    input()
+                                        .r#type("text")
+                                        .class("form-control")
+                                        .id("ref1")
+                                        .name("code")
+                                        .attr("autocomplete", "one-time-code")
+                                },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, totpCodeRequired))
+                                },
+                            )),
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-primary")
+                                    .child(t![i18n, enableTwoFactor])
+                            },
+                            { div().class("mt-2").child(message) },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            ))
+        },
+    ))
+}
+
+fn enabled_view(status: TotpStatus, disable_totp: ServerAction<DisableTotp>) -> impl IntoView {
+    let i18n = use_i18n();
+
+    let message = move || match disable_totp.value().get() {
+        Some(Ok(response)) => match response.error {
+            None => div().hidden(true).into_any(),
+            Some(ApiError::InvalidCredentials) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, wrongCurrentPassword))
+                .into_any(),
+            Some(err) => div()
+                .class("alert alert-danger")
+                .child(t!(i18n, serverError, error = err.to_string()))
+                .into_any(),
+        },
+        Some(Err(err)) => div()
+            .class("alert alert-danger")
+            .child(t!(i18n, serverError, error = err.to_string()))
+            .into_any(),
+        None => div().hidden(true).into_any(),
+    };
+
+    let validated_on_client = move |ev: SubmitEvent| {
+        let data = match DisableTotp::from_event(&ev) {
+            Err(_) => {
+                ev.prevent_default();
+                return;
+            }
+            Ok(data) => data,
+        };
+        if !length_validated(&data.password, PASSWORD_MAX_LENGTH) {
+            let form: HtmlFormElement = ev.target().unwrap().unchecked_into();
+            show_error(&ev, &form, 0);
+        }
+    };
+
+    div().child((
+        {
+            p().child(t![
+                i18n,
+                twoFactorEnabled,
+                codes = status.recovery_codes_left
+            ])
+        },
+        {
+            ActionForm(
+                ActionFormProps::builder()
+                    .action(disable_totp)
+                    .children(ToChildren::to_children(move || {
+                        (
+                            csrf_field(),
+                            div().class("mb-3 col-xs-1 col-xl-2").child((
+                                {
+                                    label()
+                                        .class("form-label")
+                                        .r#for("ref1")
+                                        .child(t![i18n, currentPassword])
+                                },
+                                {
+                                    input()
+                                        .r#type("password")
+                                        .class("form-control")
+                                        .id("ref1")
+                                        .name("password")
+                                        .required(true)
+                                        .maxlength(PASSWORD_MAX_LENGTH as i64)
+                                },
+                                {
+                                    div()
+                                        .class("invalid-feedback")
+                                        .child(t!(i18n, passwordRequired))
+                                },
+                            )),
+                            {
+                                button()
+                                    .r#type("submit")
+                                    .class("btn btn-outline-danger")
+                                    .child(t![i18n, disableTwoFactor])
+                            },
+                            { div().class("mt-2").child(message) },
+                        )
+                    }))
+                    .build(),
+            )
+            .attr("novalidate", "true")
+            .add_any_attr(event::on(
+                event::capture(event::submit),
+                validated_on_client,
+            ))
+        },
+    ))
+}
+
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn get_totp_status() -> Result<ApiResponse<TotpStatus>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+
+    let status_row = match query!(
+        "\
+            SELECT account.totp_enabled_at, COUNT(totp_recovery_code.id) AS \"recovery_codes_left!\" \
+            FROM account \
+            LEFT JOIN totp_recovery_code \
+                ON totp_recovery_code.account_id = account.id \
+                AND totp_recovery_code.used_at IS NULL \
+            WHERE account.id = $1 \
+            GROUP BY account.id\
+        ",
+        auth.account_id
+    )
+    .fetch_one(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting TOTP status: {}",
+                err
+            )))
+        }
+        Ok(row) => row,
+    };
+
+    Ok(auth.response(TotpStatus {
+        enabled: status_row.totp_enabled_at.is_some(),
+        recovery_codes_left: status_row.recovery_codes_left,
+    }))
+}
+
+/// Generates a new secret for the logged-in user, which is used once the enrollment has been
+/// confirmed by `confirm_totp_enrollment`.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn start_totp_enrollment() -> Result<ApiResponse<TotpEnrollment>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::server_utils::configuration::TotpSettings;
+    use crate::server_utils::totp::{generate_secret, new_totp};
+    use actix_web::web::Data;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let totp_settings = use_context::<Data<TotpSettings>>().expect("No TOTP settings?");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_own_session() {
+        return return_early(err);
+    }
+
+    let secret = match generate_secret() {
+        Err(err) => {
+            log!(Level::Error, "Error generating TOTP secret: {}", err);
+            return return_early(ApiError::UnexpectedError("Random Error".to_string()));
+        }
+        Ok(secret) => secret,
+    };
+    // an enabled 2FA has to be disabled first
+    let account_row = match query!(
+        "\
+            UPDATE account \
+            SET totp_secret = $2 \
+            WHERE id = $1 AND totp_enabled_at IS NULL \
+            RETURNING username\
+        ",
+        auth.account_id,
+        secret
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error updating TOTP secret: {}",
+                err
+            )))
+        }
+        Ok(None) => {
+            return return_early(ApiError::UnexpectedError(
+                "Two-factor authentication is already enabled".to_string(),
+            ))
+        }
+        Ok(Some(row)) => row,
+    };
+    let enrollment =
+        new_totp(&secret, &totp_settings.issuer, &account_row.username).and_then(|totp| {
+            Ok(TotpEnrollment {
+                otpauth_uri: totp.get_url(),
+                qr_code: totp.get_qr_base64()?,
+                secret,
+            })
+        });
+
+    match enrollment {
+        Ok(enrollment) => Ok(auth.response(enrollment)),
+        Err(err) => {
+            log!(Level::Error, "Error creating TOTP enrollment: {}", err);
+            return_early(ApiError::UnexpectedError("TOTP Error".to_string()))
+        }
+    }
+}
+
+/// Enables 2FA for the logged-in user, if the code matches the secret of the started enrollment.
+///
+/// Returns the recovery codes, which replace all earlier ones. They are only stored hashed,
+/// so this is the only time they can be shown.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn confirm_totp_enrollment(
+    code: String,
+) -> Result<ApiResponse<Vec<String>>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use crate::server_utils::configuration::TotpSettings;
+    use crate::server_utils::totp::{
+        find_matching_step, generate_recovery_codes, new_totp, normalize_code,
+    };
+    use actix_web::web::Data;
+    use chrono::Utc;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let totp_settings = use_context::<Data<TotpSettings>>().expect("No TOTP settings?");
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_own_session() {
+        return return_early(err);
+    }
+
+    let account_row = match query!(
+        "\
+            SELECT username, totp_secret \
+            FROM account \
+            WHERE id = $1 AND totp_enabled_at IS NULL\
+        ",
+        auth.account_id
+    )
+    .fetch_optional(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting account: {}",
+                err
+            )))
+        }
+        Ok(row) => row,
+    };
+    let (username, secret) = match account_row {
+        Some(row) if row.totp_secret.is_some() => (row.username, row.totp_secret.unwrap()),
+        _ => {
+            return return_early(ApiError::UnexpectedError(
+                "No two-factor enrollment started".to_string(),
+            ))
+        }
+    };
+    let totp = match new_totp(&secret, &totp_settings.issuer, &username) {
+        Err(err) => {
+            log!(Level::Error, "Invalid TOTP of {}: {}", username, err);
+            return return_early(ApiError::UnexpectedError("TOTP Error".to_string()));
+        }
+        Ok(totp) => totp,
+    };
+    let step = match find_matching_step(&totp, &normalize_code(&code), None) {
+        None => return return_early(ApiError::InvalidTotpCode),
+        Some(step) => step,
+    };
+    let recovery_codes = match generate_recovery_codes() {
+        Err(err) => {
+            log!(Level::Error, "Error generating recovery codes: {}", err);
+            return return_early(ApiError::UnexpectedError("Random Error".to_string()));
+        }
+        Ok(recovery_codes) => recovery_codes,
+    };
+
+    let enable_result = async {
+        let mut tx = db_pool.begin().await?;
+        query!(
+            "\
+                UPDATE account \
+                SET totp_enabled_at = $2, totp_last_used_step = $3 \
+                WHERE id = $1\
+            ",
+            auth.account_id,
+            Utc::now().naive_utc(),
+            step
+        )
+        .execute(&mut *tx)
+        .await?;
+        query!(
+            "\
+                DELETE FROM totp_recovery_code \
+                WHERE account_id = $1\
+            ",
+            auth.account_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for (_, code_hash) in recovery_codes.iter() {
+            query!(
+                "\
+                    INSERT INTO totp_recovery_code (account_id, code_hash) \
+                    VALUES ($1, $2)\
+                ",
+                auth.account_id,
+                code_hash
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+    .await;
+    if let Err(err) = enable_result {
+        return return_early(ApiError::DbError(format!("Error enabling TOTP: {}", err)));
+    }
+    log!(Level::Info, "Enabled TOTP: {}", username);
+
+    Ok(auth.response(recovery_codes.into_iter().map(|(code, _)| code).collect()))
+}
+
+/// Disables 2FA for the logged-in user, who has to confirm it with the password.
+#[server(client = crate::client::AddAuthHeaderClient)]
+pub async fn disable_totp(password: String) -> Result<ApiResponse<()>, ServerFnError> {
+    use crate::api::auth_context::AuthContext;
+    use crate::api::error::return_early;
+    use actix_web::web::Data;
+    use bcrypt::verify;
+    use log::{log, Level};
+    use sqlx::query;
+    use sqlx::{Pool, Postgres};
+
+    let db_pool = match use_context::<Data<Pool<Postgres>>>() {
+        None => {
+            log!(Level::Warn, "No database pool found in context");
+            return return_early(ApiError::DBConnectionError);
+        }
+        Some(db_pool) => db_pool,
+    };
+    let auth = match AuthContext::extract().await {
+        Err(err) => return return_early(err),
+        Ok(auth) => auth,
+    };
+    if let Err(err) = auth.require_own_session() {
+        return return_early(err);
+    }
+
+    let account_row = match query!(
+        "\
+            SELECT pw_hash \
+            FROM account \
+            WHERE id = $1\
+        ",
+        auth.account_id
+    )
+    .fetch_one(&**db_pool)
+    .await
+    {
+        Err(err) => {
+            return return_early(ApiError::DbError(format!(
+                "Error selecting account: {}",
+                err
+            )))
+        }
+        Ok(row) => row,
+    };
+    let verified = verify(&password, &account_row.pw_hash).unwrap_or_else(|e| {
+        log!(Level::Warn, "Error verifying password: {}", e);
+        false
+    });
+    if !verified {
+        return return_early(ApiError::InvalidCredentials);
+    }
+
+    let disable_result = async {
+        let mut tx = db_pool.begin().await?;
+        query!(
+            "\
+                UPDATE account \
+                SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_used_step = NULL \
+                WHERE id = $1\
+            ",
+            auth.account_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        query!(
+            "\
+                DELETE FROM totp_recovery_code \
+                WHERE account_id = $1\
+            ",
+            auth.account_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await;
+
+    match disable_result {
+        Ok(_) => {
+            log!(Level::Info, "Disabled TOTP of account {}", auth.account_id);
+            Ok(auth.response(()))
+        }
+        Err(err) => return_early(ApiError::DbError(format!("Error disabling TOTP: {}", err))),
+    }
+}
@@ -0,0 +1,268 @@
+use crate::server_utils::configuration::{AuthBackendSettings, FakeUserSettings};
+use bcrypt::verify;
+use futures_util::future::BoxFuture;
+use ldap3::{dn_escape, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use log::{log, Level};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The LDAP result code for a wrong password or an unknown DN.
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+
+/// The local account a password is checked for.
+pub struct LoginAccount {
+    pub username: String,
+    pub pw_hash: String,
+}
+
+pub enum AuthOutcome {
+    /// The password is right. `name` is the display name the backend knows, if it knows one.
+    Accepted {
+        name: Option<String>,
+    },
+    Rejected,
+}
+
+#[derive(Debug)]
+pub struct AuthBackendError(String);
+
+impl Display for AuthBackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Checks the password of a login.
+///
+/// Server fns get the configured implementation from context as `Data<dyn AuthBackend>`.
+pub trait AuthBackend: Send + Sync {
+    fn authenticate<'a>(
+        &'a self,
+        account: &'a LoginAccount,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<AuthOutcome, AuthBackendError>>;
+}
+
+/// Creates the chain of the configured backends.
+pub fn new_auth_backend(
+    settings: &[AuthBackendSettings],
+) -> Result<Arc<dyn AuthBackend>, AuthBackendError> {
+    if settings.is_empty() {
+        return Err(AuthBackendError("No auth backend configured".to_string()));
+    }
+    let backends = settings
+        .iter()
+        .map(|backend_settings| -> Box<dyn AuthBackend> {
+            match backend_settings {
+                AuthBackendSettings::Database => Box::new(DatabaseAuthBackend),
+                AuthBackendSettings::Ldap {
+                    url,
+                    bind_dn_template,
+                    name_attribute,
+                    timeout_secs,
+                } => Box::new(LdapAuthBackend::new(
+                    url,
+                    bind_dn_template,
+                    name_attribute,
+                    *timeout_secs,
+                )),
+                AuthBackendSettings::Fake { users } => {
+                    log!(
+                        Level::Warn,
+                        "Fake auth backend configured, meant for tests only"
+                    );
+                    Box::new(FakeAuthBackend::new(users))
+                }
+            }
+        })
+        .collect();
+
+    Ok(Arc::new(AuthBackendChain { backends }))
+}
+
+/// Asks its backends in order until one accepts the password.
+///
+/// A failing backend doesn't stop the chain. But if none accepts the password, the login fails
+/// with the error instead of counting as a failed login, so an unreachable LDAP server doesn't
+/// lock accounts.
+pub struct AuthBackendChain {
+    backends: Vec<Box<dyn AuthBackend>>,
+}
+
+impl AuthBackend for AuthBackendChain {
+    fn authenticate<'a>(
+        &'a self,
+        account: &'a LoginAccount,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<AuthOutcome, AuthBackendError>> {
+        Box::pin(async move {
+            let mut first_error = None;
+            for backend in self.backends.iter() {
+                match backend.authenticate(account, password).await {
+                    Ok(AuthOutcome::Accepted { name }) => {
+                        return Ok(AuthOutcome::Accepted { name });
+                    }
+                    Ok(AuthOutcome::Rejected) => {}
+                    Err(err) => {
+                        log!(Level::Warn, "Auth backend failed: {}", err);
+                        first_error.get_or_insert(err);
+                    }
+                }
+            }
+
+            match first_error {
+                None => Ok(AuthOutcome::Rejected),
+                Some(err) => Err(err),
+            }
+        })
+    }
+}
+
+/// Checks the password against the bcrypt hash of the account.
+pub struct DatabaseAuthBackend;
+
+impl AuthBackend for DatabaseAuthBackend {
+    fn authenticate<'a>(
+        &'a self,
+        account: &'a LoginAccount,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<AuthOutcome, AuthBackendError>> {
+        Box::pin(async move {
+            let verified = verify(password, &account.pw_hash).unwrap_or_else(|e| {
+                log!(Level::Warn, "Error verifying password: {}", e);
+                false
+            });
+
+            if verified {
+                Ok(AuthOutcome::Accepted { name: None })
+            } else {
+                Ok(AuthOutcome::Rejected)
+            }
+        })
+    }
+}
+
+/// Checks the password by a simple bind as the DN of the username.
+///
+/// `bind_dn_template` contains `{username}`, which is replaced by the escaped username. The
+/// `name_attribute` of the bound entry is read as the name to sync.
+pub struct LdapAuthBackend {
+    url: String,
+    bind_dn_template: String,
+    name_attribute: String,
+    timeout: Duration,
+}
+
+impl LdapAuthBackend {
+    pub fn new(url: &str, bind_dn_template: &str, name_attribute: &str, timeout_secs: u8) -> Self {
+        Self {
+            url: url.to_string(),
+            bind_dn_template: bind_dn_template.to_string(),
+            name_attribute: name_attribute.to_string(),
+            timeout: Duration::from_secs(timeout_secs as u64),
+        }
+    }
+}
+
+impl AuthBackend for LdapAuthBackend {
+    fn authenticate<'a>(
+        &'a self,
+        account: &'a LoginAccount,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<AuthOutcome, AuthBackendError>> {
+        Box::pin(async move {
+            // a bind without password is an anonymous bind, which always succeeds
+            if password.is_empty() {
+                return Ok(AuthOutcome::Rejected);
+            }
+            let conn_settings = LdapConnSettings::new().set_conn_timeout(self.timeout);
+            let (conn, mut ldap) = LdapConnAsync::with_settings(conn_settings, &self.url)
+                .await
+                .map_err(|err| AuthBackendError(format!("Couldn't connect to LDAP: {}", err)))?;
+            ldap3::drive!(conn);
+            ldap.with_timeout(self.timeout);
+
+            let bind_dn = self
+                .bind_dn_template
+                .replace("{username}", &dn_escape(account.username.as_str()));
+            let bind_result = ldap
+                .simple_bind(&bind_dn, password)
+                .await
+                .map_err(|err| AuthBackendError(format!("LDAP bind failed: {}", err)))?;
+            if bind_result.rc == LDAP_INVALID_CREDENTIALS {
+                let _ = ldap.unbind().await;
+                return Ok(AuthOutcome::Rejected);
+            }
+            bind_result
+                .success()
+                .map_err(|err| AuthBackendError(format!("LDAP bind failed: {}", err)))?;
+
+            // the password is right, even if the name can't be read
+            ldap.with_timeout(self.timeout);
+            let name = match ldap
+                .search(
+                    &bind_dn,
+                    Scope::Base,
+                    "(objectClass=*)",
+                    vec![self.name_attribute.as_str()],
+                )
+                .await
+                .and_then(|search_result| search_result.success())
+            {
+                Err(err) => {
+                    log!(
+                        Level::Warn,
+                        "Couldn't read LDAP name of {}: {}",
+                        bind_dn,
+                        err
+                    );
+                    None
+                }
+                Ok((entries, _)) => entries.into_iter().next().and_then(|entry| {
+                    SearchEntry::construct(entry)
+                        .attrs
+                        .get(&self.name_attribute)
+                        .and_then(|values| values.first().cloned())
+                }),
+            };
+            let _ = ldap.unbind().await;
+
+            Ok(AuthOutcome::Accepted { name })
+        })
+    }
+}
+
+/// Knows a fixed list of users with plain passwords, meant for the end2end tests.
+pub struct FakeAuthBackend {
+    users: HashMap<String, FakeUserSettings>,
+}
+
+impl FakeAuthBackend {
+    pub fn new(users: &[FakeUserSettings]) -> Self {
+        Self {
+            users: users
+                .iter()
+                .map(|user| (user.username.clone(), user.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl AuthBackend for FakeAuthBackend {
+    fn authenticate<'a>(
+        &'a self,
+        account: &'a LoginAccount,
+        password: &'a str,
+    ) -> BoxFuture<'a, Result<AuthOutcome, AuthBackendError>> {
+        Box::pin(async move {
+            match self.users.get(&account.username) {
+                Some(user) if user.password == password => Ok(AuthOutcome::Accepted {
+                    name: Some(user.name.clone()),
+                }),
+                _ => Ok(AuthOutcome::Rejected),
+            }
+        })
+    }
+}
@@ -1,20 +1,43 @@
+use crate::api::auth_context::AuthContext;
 use crate::api::error::ApiError;
-use crate::api::jwt::{get_jwt_validation, JwtClaim, JwtKeys};
+use crate::api::jwt::JwtKeys;
 use crate::api::response::ApiResponse;
+use crate::model::role::Role;
+use crate::pages::login::load_user;
+use crate::server_utils::csrf::CsrfTokenHash;
+use crate::server_utils::public_endpoints::PublicEndpoints;
+use crate::server_utils::session_cookie::{removal_cookie, session_cookie, SESSION_COOKIE};
+use crate::server_utils::session_store::SessionStore;
+use crate::server_utils::token::{hash_token, API_TOKEN_PREFIX};
 use actix_web::body::{EitherBody, MessageBody};
+use actix_web::cookie::Cookie;
 use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
 use actix_web::web::Data;
-use actix_web::{http, Error, HttpMessage, HttpResponse};
+use actix_web::{http, Error, HttpMessage, HttpRequest, HttpResponse};
 use chrono::Utc;
 use futures_util::future::LocalBoxFuture;
-use jsonwebtoken::decode;
+use jsonwebtoken::errors::ErrorKind;
 use log::{log, Level};
 use sqlx::{query, Pool, Postgres};
 use std::future::{ready, Ready};
 use std::rc::Rc;
 
 /// This wraps authorization for a leptos server fn.
-pub struct Authorisation;
+///
+/// Only the server fns in `public_endpoints` can be called without a session. Depending on the
+/// `SessionTransport`, the session token is read from the `Authorization` header or the session
+/// cookie, which is refreshed with the extended session.
+pub struct Authorisation {
+    public_endpoints: Rc<PublicEndpoints>,
+}
+
+impl Authorisation {
+    pub fn new(public_endpoints: PublicEndpoints) -> Self {
+        Self {
+            public_endpoints: Rc::new(public_endpoints),
+        }
+    }
+}
 
 impl<S, B> Transform<S, ServiceRequest> for Authorisation
 where
@@ -31,13 +54,18 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(AuthorisationMiddleware {
             service: service.into(),
+            public_endpoints: self.public_endpoints.clone(),
         }))
     }
 }
 
+/// The session cookie with the token of the extended session, set after the server fn.
+struct RefreshedCookie(Cookie<'static>);
+
 pub struct AuthorisationMiddleware<S> {
     // wrap with Rc to get static lifetime for async function calls in `call`
     service: Rc<S>,
+    public_endpoints: Rc<PublicEndpoints>,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthorisationMiddleware<S>
@@ -55,168 +83,8 @@ where
     fn call(&self, req: ServiceRequest) -> Self::Future {
         // to use it in the closure for async function calls
         let srv = self.service.clone();
-        // grab url path from request to care of 'login'
-        let is_login = req.path().starts_with("/api/login");
-        // grab url path from request to care of 'get_message'
-        let is_get_message = req.path().starts_with("/api/get_message");
-        let needs_authorization = !(is_login || is_get_message);
-
-        async fn authorize(req: &ServiceRequest, db_pool: &Pool<Postgres>) -> Option<ApiError> {
-            let jwt_keys = match req.app_data::<Data<JwtKeys>>() {
-                None => {
-                    log!(
-                        Level::Error,
-                        "{}",
-                        get_info(req, "No JWT keys found in request context: ".to_string())
-                    );
-                    return Some(ApiError::UnexpectedError(format!(
-                        "Error time: {}",
-                        Utc::now(),
-                    )));
-                }
-                Some(keys) => keys,
-            };
-            let auth_header = match req.headers().get("Authorization") {
-                None => {
-                    log!(
-                        Level::Trace,
-                        "{}",
-                        get_info(req, "No Authorization header: ".to_string())
-                    );
-                    return Some(ApiError::Unauthorized);
-                }
-                Some(header_value) => {
-                    if header_value.is_empty() {
-                        log!(
-                            Level::Trace,
-                            "{}",
-                            get_info(req, "Empty Authorization header: ".to_string())
-                        );
-                        return Some(ApiError::Unauthorized);
-                    } else {
-                        match header_value.to_str() {
-                            Err(_) => {
-                                log!(
-                                    Level::Trace,
-                                    "Couldn't convert authorization header value to &str: {}",
-                                    get_info(req, "Connection Info: ".to_string())
-                                );
-                                return Some(ApiError::Unauthorized);
-                            }
-                            Ok(value) => value,
-                        }
-                    }
-                }
-            };
-            let bearer_matcher = "Bearer ";
-            let token = if !auth_header.starts_with(bearer_matcher) {
-                log!(
-                    Level::Trace,
-                    "{}",
-                    get_info(
-                        req,
-                        "Authorization header has no Bearer token: ".to_string()
-                    )
-                );
-                return Some(ApiError::Unauthorized);
-            } else {
-                &auth_header[bearer_matcher.len()..]
-            };
-            let token_decode_result =
-                decode::<JwtClaim>(&token, &jwt_keys.decode_key, &get_jwt_validation());
-            let session_id_claim = match token_decode_result {
-                Err(_) => {
-                    log!(
-                        Level::Trace,
-                        "{}",
-                        get_info(
-                            req,
-                            "Authorization header has a non valid signature or payload: "
-                                .to_string()
-                        )
-                    );
-                    return Some(ApiError::Unauthorized);
-                }
-                Ok(claim) => claim,
-            };
-            let session_id = match session_id_claim.claims.try_into_uuid() {
-                Err(_) => {
-                    log!(
-                        Level::Trace,
-                        "{}",
-                        get_info(req, "Payload is not an UUID: ".to_string())
-                    );
-                    return Some(ApiError::Unauthorized);
-                }
-                Ok(uuid) => uuid,
-            };
-
-            // authenticate
-            let session_row = match query!(
-                "\
-                SELECT account_id, expires_at FROM session WHERE id = $1\
-                ",
-                session_id
-            )
-            .fetch_optional(db_pool)
-            .await
-            {
-                Err(err) => {
-                    log!(
-                        Level::Trace,
-                        "DB returned an error in authorize: \n\tReq Info: {} \n\tOriginal Error: {}",
-                        get_info(req, "".to_string()),
-                        err
-                    );
-                    return Some(ApiError::Unauthorized);
-                }
-                Ok(session_id_option) => match session_id_option {
-                    None => {
-                        log!(
-                            Level::Warn,
-                            "{}",
-                            get_info(req, "No session found:".to_string())
-                        );
-                        return Some(ApiError::Unauthorized);
-                    }
-                    Some(row) => row,
-                },
-            };
-            // check whether expired
-            if session_row.expires_at.and_utc().timestamp() < Utc::now().timestamp() {
-                return Some(ApiError::Expired);
-            }
-            // now we know the session is authenticated and not expired, so update session
-            let account_id = session_row.account_id;
-            let updated_session_row_result = query!(
-                "\
-                UPDATE session SET expires_at = DEFAULT \
-                WHERE id = $1 \
-                RETURNING expires_at \
-                ",
-                session_id
-            )
-            .fetch_one(db_pool)
-            .await;
-            let updated_session_row = match updated_session_row_result {
-                Err(_) => {
-                    log!(
-                        Level::Warn,
-                        "{}",
-                        get_info(req, "Could not update session row".to_string())
-                    );
-                    return Some(ApiError::Unauthorized);
-                }
-                Ok(row) => row,
-            };
-
-            req.extensions_mut().insert(token.to_string());
-            req.extensions_mut().insert(account_id);
-            req.extensions_mut()
-                .insert(updated_session_row.expires_at.and_utc().timestamp());
-
-            None
-        }
+        // deny by default, only exactly matching paths are public
+        let needs_authorization = !self.public_endpoints.contains(req.path());
 
         Box::pin(async move {
             if needs_authorization {
@@ -227,7 +95,7 @@ where
                             Level::Error,
                             "{}: {}",
                             error_msg,
-                            get_info(&req, "Request Info".to_string())
+                            get_info(req.request(), "Request Info".to_string())
                         );
                         let new_body = ApiResponse {
                             expires_at: 0,
@@ -244,41 +112,480 @@ where
                     Some(pool) => pool.get_ref(),
                 };
 
-                let auth_option = authorize(&req, db_pool).await;
-                match auth_option {
-                    Some(err) => {
-                        let new_body = ApiResponse {
-                            expires_at: 0,
-                            token: "".to_string(),
-                            error: Some(err),
-                            data: (),
-                        };
-                        let new_http_response = HttpResponse::Ok().json(new_body);
-                        let new_service_response =
-                            ServiceResponse::new(req.request().clone(), new_http_response);
-                        return Ok(new_service_response.map_into_right_body());
+                let auth_option = authorize(req.request(), db_pool).await;
+                if let Some(err) = auth_option {
+                    // the browser shouldn't keep sending an invalid or expired session
+                    let remove_cookie = req.cookie(SESSION_COOKIE).is_some()
+                        && matches!(err, ApiError::Unauthorized | ApiError::Expired);
+                    let new_body = ApiResponse {
+                        expires_at: 0,
+                        token: "".to_string(),
+                        error: Some(err),
+                        data: (),
+                    };
+                    let mut new_http_response = HttpResponse::Ok();
+                    if remove_cookie {
+                        new_http_response.cookie(removal_cookie());
                     }
-                    None => (),
+                    let new_http_response = new_http_response.json(new_body);
+                    let new_service_response =
+                        ServiceResponse::new(req.request().clone(), new_http_response);
+                    return Ok(new_service_response.map_into_right_body());
                 }
             }
             // call other middleware and handler and get the response
-            let res = srv.call(req).await?;
-            let _request = res.request().clone();
+            let mut res = srv.call(req).await?;
             if needs_authorization {
-                // add code here if it is to be called after the server fn
+                refresh_session_cookie(&mut res);
             }
             Ok(res.map_into_left_body())
         })
     }
 }
 
-fn get_info(req: &ServiceRequest, msg: String) -> String {
+/// This resolves the session of page requests for the server-side rendering.
+///
+/// Browsers only send the session cookie with page requests, so bearer tokens can't be
+/// resolved. The `User` of the session is inserted into the request extensions, where `App`
+/// takes it from, requests with an invalid session are rendered like anonymous ones.
+pub struct PageAuthorisation;
+
+impl<S, B> Transform<S, ServiceRequest> for PageAuthorisation
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = PageAuthorisationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(PageAuthorisationMiddleware {
+            service: service.into(),
+        }))
+    }
+}
+
+pub struct PageAuthorisationMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for PageAuthorisationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = self.service.clone();
+        // server fns, the files and the assets don't render pages
+        let is_page_request = req.method() == http::Method::GET
+            && req
+                .headers()
+                .get(http::header::ACCEPT)
+                .and_then(|accept| accept.to_str().ok())
+                .is_some_and(|accept| accept.contains("text/html"));
+        let needs_authorization = is_page_request && req.cookie(SESSION_COOKIE).is_some();
+
+        Box::pin(async move {
+            let mut remove_cookie = false;
+            if needs_authorization {
+                match req.app_data::<Data<Pool<Postgres>>>() {
+                    None => log!(
+                        Level::Error,
+                        "{}",
+                        get_info(req.request(), "No DB pool found in request: ".to_string())
+                    ),
+                    Some(db_pool) => match authorize(req.request(), db_pool).await {
+                        Some(err) => {
+                            remove_cookie =
+                                matches!(err, ApiError::Unauthorized | ApiError::Expired);
+                        }
+                        None => insert_user(req.request(), db_pool).await,
+                    },
+                }
+            }
+
+            let mut res = srv.call(req).await?;
+            if remove_cookie {
+                if let Err(err) = res.response_mut().add_cookie(&removal_cookie()) {
+                    log!(Level::Error, "Couldn't remove session cookie: {}", err);
+                }
+            } else if needs_authorization {
+                refresh_session_cookie(&mut res);
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Inserts the `User` of the authorised session, the page is rendered anonymously without it.
+async fn insert_user(req: &HttpRequest, db_pool: &Pool<Postgres>) {
+    let auth = match AuthContext::from_request(req) {
+        Err(_) => return,
+        Ok(auth) => auth,
+    };
+    match load_user(db_pool, &auth).await {
+        Err(err) => log!(
+            Level::Error,
+            "Couldn't load user {} for rendering: {}",
+            auth.account_id,
+            err
+        ),
+        Ok(user) => {
+            req.extensions_mut().insert(user);
+        }
+    }
+}
+
+/// Sets the session cookie of the extended session.
+///
+/// It's left out, if the server fn has set or removed the cookie itself, e.g., on logout.
+fn refresh_session_cookie<B>(res: &mut ServiceResponse<B>) {
+    let refreshed_cookie = res
+        .request()
+        .extensions()
+        .get::<RefreshedCookie>()
+        .map(|refreshed_cookie| refreshed_cookie.0.clone());
+    if let Some(cookie) = refreshed_cookie {
+        let sets_cookie = res
+            .response()
+            .cookies()
+            .any(|cookie| cookie.name() == SESSION_COOKIE);
+        if !sets_cookie {
+            if let Err(err) = res.response_mut().add_cookie(&cookie) {
+                log!(Level::Error, "Couldn't refresh session cookie: {}", err);
+            }
+        }
+    }
+}
+
+/// Authenticates the session of the request and inserts its `AuthContext`.
+async fn authorize(req: &HttpRequest, db_pool: &Pool<Postgres>) -> Option<ApiError> {
+    let jwt_keys = match req.app_data::<Data<JwtKeys>>() {
+        None => {
+            log!(
+                Level::Error,
+                "{}",
+                get_info(req, "No JWT keys found in request context: ".to_string())
+            );
+            return Some(ApiError::UnexpectedError(format!(
+                "Error time: {}",
+                Utc::now(),
+            )));
+        }
+        Some(keys) => keys,
+    };
+    let token = match req.headers().get("Authorization") {
+        None => match req.cookie(SESSION_COOKIE) {
+            Some(cookie) if jwt_keys.transport.uses_cookie() => cookie.value().to_string(),
+            _ => {
+                log!(
+                    Level::Trace,
+                    "{}",
+                    get_info(
+                        req,
+                        "No Authorization header or session cookie: ".to_string()
+                    )
+                );
+                return Some(ApiError::Unauthorized);
+            }
+        },
+        Some(header_value) => {
+            let auth_header = if header_value.is_empty() {
+                log!(
+                    Level::Trace,
+                    "{}",
+                    get_info(req, "Empty Authorization header: ".to_string())
+                );
+                return Some(ApiError::Unauthorized);
+            } else {
+                match header_value.to_str() {
+                    Err(_) => {
+                        log!(
+                            Level::Trace,
+                            "Couldn't convert authorization header value to &str: {}",
+                            get_info(req, "Connection Info: ".to_string())
+                        );
+                        return Some(ApiError::Unauthorized);
+                    }
+                    Ok(value) => value,
+                }
+            };
+            let bearer_matcher = "Bearer ";
+            let token = if !auth_header.starts_with(bearer_matcher) {
+                log!(
+                    Level::Trace,
+                    "{}",
+                    get_info(
+                        req,
+                        "Authorization header has no Bearer token: ".to_string()
+                    )
+                );
+                return Some(ApiError::Unauthorized);
+            } else {
+                &auth_header[bearer_matcher.len()..]
+            };
+            if token.starts_with(API_TOKEN_PREFIX) {
+                return authorize_api_token(req, db_pool, token).await;
+            }
+            if !jwt_keys.transport.uses_bearer() {
+                log!(
+                    Level::Trace,
+                    "{}",
+                    get_info(
+                        req,
+                        "Session tokens are only accepted as cookie: ".to_string()
+                    )
+                );
+                return Some(ApiError::Unauthorized);
+            }
+            token.to_string()
+        }
+    };
+    let token_decode_result = jwt_keys.decode_token(&token);
+    let session_id_claim = match token_decode_result {
+        Err(err) if *err.kind() == ErrorKind::ExpiredSignature => {
+            log!(
+                Level::Trace,
+                "{}",
+                get_info(
+                    req,
+                    "Authorization header has an expired token: ".to_string()
+                )
+            );
+            return Some(ApiError::Expired);
+        }
+        Err(_) => {
+            log!(
+                Level::Trace,
+                "{}",
+                get_info(
+                    req,
+                    "Authorization header has a non valid signature or payload: ".to_string()
+                )
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(claim) => claim,
+    };
+    if session_id_claim.claims.is_issued_in_future(jwt_keys.leeway) {
+        log!(
+            Level::Trace,
+            "{}",
+            get_info(req, "Token is issued in the future: ".to_string())
+        );
+        return Some(ApiError::Unauthorized);
+    }
+    let session_id = match session_id_claim.claims.try_into_uuid() {
+        Err(_) => {
+            log!(
+                Level::Trace,
+                "{}",
+                get_info(req, "Payload is not an UUID: ".to_string())
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(uuid) => uuid,
+    };
+
+    // authenticate
+    let session_store = match req.app_data::<Data<dyn SessionStore>>() {
+        None => {
+            log!(
+                Level::Error,
+                "{}",
+                get_info(
+                    req,
+                    "No session store found in request context: ".to_string()
+                )
+            );
+            return Some(ApiError::UnexpectedError(format!(
+                "Error time: {}",
+                Utc::now(),
+            )));
+        }
+        Some(session_store) => session_store,
+    };
+    let session = match session_store.find(session_id).await {
+        Err(err) => {
+            log!(
+                Level::Trace,
+                "Session store returned an error in authorize: \n\tReq Info: {} \n\tOriginal Error: {}",
+                get_info(req, "".to_string()),
+                err
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(None) => {
+            log!(
+                Level::Warn,
+                "{}",
+                get_info(req, "No session found:".to_string())
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(Some(session)) => session,
+    };
+    // check whether expired
+    if session.expires_at.and_utc().timestamp() < Utc::now().timestamp() {
+        return Some(ApiError::Expired);
+    }
+    // the store doesn't know accounts, so the role is taken from the account itself,
+    // sessions of a disabled account end even if a store missed the account being disabled
+    let account_id = session.account_id;
+    let account_row = match query!(
+        "\
+        SELECT role as \"role: Role\" \
+        FROM account \
+        WHERE id = $1 AND disabled_at IS NULL\
+        ",
+        account_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    {
+        Err(err) => {
+            log!(
+                Level::Trace,
+                "DB returned an error in authorize: \n\tReq Info: {} \n\tOriginal Error: {}",
+                get_info(req, "".to_string()),
+                err
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(None) => {
+            log!(
+                Level::Warn,
+                "{}",
+                get_info(req, "No enabled account of the session found:".to_string())
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(Some(row)) => row,
+    };
+    // now we know the session is authenticated and not expired, so update session
+    let updated_expires_at = match session_store.touch(session_id).await {
+        Err(_) | Ok(None) => {
+            log!(
+                Level::Warn,
+                "{}",
+                get_info(req, "Could not update session row".to_string())
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(Some(expires_at)) => expires_at,
+    };
+
+    // the token expires with the session, so the extended session needs a new one
+    let expires_at = updated_expires_at.and_utc().timestamp();
+    let token = match jwt_keys.encode_token(session_id, expires_at) {
+        Err(err) => {
+            log!(Level::Error, "Couldn't encode JWT: {}", err);
+            return Some(ApiError::UnexpectedError(format!(
+                "Error time: {}",
+                Utc::now(),
+            )));
+        }
+        Ok(token) => token,
+    };
+
+    req.extensions_mut()
+        .insert(CsrfTokenHash(session.csrf_token_hash));
+    if jwt_keys.transport.uses_cookie() {
+        req.extensions_mut()
+            .insert(RefreshedCookie(session_cookie(&token, expires_at)));
+    }
+    req.extensions_mut().insert(AuthContext {
+        account_id,
+        session_id,
+        via_api_token: false,
+        role: account_row.role,
+        impersonator_id: session.impersonator_id,
+        // without bearer tokens, the token mustn't get into the response body
+        token: if jwt_keys.transport.uses_bearer() {
+            token
+        } else {
+            "".to_string()
+        },
+        expires_at,
+    });
+
+    None
+}
+
+/// Authenticates a personal API token, which neither slides nor starts a session.
+async fn authorize_api_token(
+    req: &HttpRequest,
+    db_pool: &Pool<Postgres>,
+    token: &str,
+) -> Option<ApiError> {
+    let api_token_row = match query!(
+        "\
+        UPDATE api_token SET last_used_at = current_timestamp \
+        FROM account \
+        WHERE api_token.token_hash = $1 AND account.id = api_token.account_id \
+            AND (api_token.expires_at IS NULL OR api_token.expires_at > current_timestamp) \
+            AND account.disabled_at IS NULL \
+        RETURNING api_token.id, api_token.account_id, api_token.expires_at, \
+            account.role as \"role: Role\"\
+        ",
+        hash_token(token)
+    )
+    .fetch_optional(db_pool)
+    .await
+    {
+        Err(err) => {
+            log!(
+                Level::Trace,
+                "DB returned an error in authorize_api_token: \n\tReq Info: {} \n\tOriginal Error: {}",
+                get_info(req, "".to_string()),
+                err
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(None) => {
+            log!(
+                Level::Warn,
+                "{}",
+                get_info(req, "No valid API token found:".to_string())
+            );
+            return Some(ApiError::Unauthorized);
+        }
+        Ok(Some(row)) => row,
+    };
+
+    req.extensions_mut().insert(AuthContext {
+        account_id: api_token_row.account_id,
+        session_id: api_token_row.id,
+        via_api_token: true,
+        role: api_token_row.role,
+        impersonator_id: None,
+        token: token.to_string(),
+        expires_at: api_token_row
+            .expires_at
+            .map_or(0, |expires_at| expires_at.and_utc().timestamp()),
+    });
+
+    None
+}
+
+fn get_info(req: &HttpRequest, msg: String) -> String {
     let header = match req.headers().get(http::header::AUTHORIZATION) {
         None => "Missing".to_string(),
-        Some(header) => header
-            .to_str()
-            .unwrap_or_else(|_| "Couldn't convert to string")
-            .to_string(),
+        Some(header) => match header.to_str() {
+            Err(_) => "Couldn't convert to string".to_string(),
+            Ok(header) => redact_authorization(header),
+        },
     };
     let ip = match req.peer_addr() {
         None => "Not found in connection".to_string(),
@@ -287,3 +594,15 @@ fn get_info(req: &ServiceRequest, msg: String) -> String {
 
     format!("{} \n\tAuthorization header: {}\n\tIP:{}", msg, header, ip)
 }
+
+/// Keeps only the scheme and the kind of token of an `Authorization` header for the logs.
+///
+/// The tokens themselves grant access, API tokens even for a long time.
+fn redact_authorization(header: &str) -> String {
+    let (scheme, token) = header.split_once(' ').unwrap_or(("", header));
+    if token.starts_with(API_TOKEN_PREFIX) {
+        format!("{} {}<redacted>", scheme, API_TOKEN_PREFIX)
+    } else {
+        format!("{} <redacted>", scheme)
+    }
+}
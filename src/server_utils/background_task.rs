@@ -1,33 +1,82 @@
 use crate::server_utils::configuration::Settings;
 use crate::server_utils::logging::Logger;
+use crate::server_utils::session_store::SessionStore;
 use chrono::{NaiveDateTime, TimeDelta};
 use log::{log, Level};
 use sqlx::{query, Pool, Postgres};
+use std::sync::Arc;
 use tokio_cron_scheduler::{Job, JobScheduler, JobSchedulerError};
 
-/// The background task handling session cleanup in the database.
-async fn session_cleanup_task(expiry_mins: u8, db_pool: Pool<Postgres>) {
+/// The background task handling session cleanup in the session store.
+async fn session_cleanup_task(expiry_mins: u8, session_store: Arc<dyn SessionStore>) {
     let now = chrono::Utc::now();
     // delete sessions with twice the expiry time ago
     let ready_to_delete = now - TimeDelta::minutes((expiry_mins * 2) as i64);
     let ready_to_delete: NaiveDateTime =
         NaiveDateTime::new(ready_to_delete.date_naive(), ready_to_delete.time());
-    let query_result = query!(
+    let purge_result = session_store.purge_expired(ready_to_delete).await;
+
+    match purge_result {
+        Err(e) => {
+            log!(Level::Warn, "Failed to cleanup sessions: {}", e);
+        }
+        Ok(count) => {
+            log!(Level::Debug, "cleaned up {} sessions", count);
+        }
+    }
+}
+
+/// The background task deleting used and expired password reset tokens, login challenges,
+/// API tokens and unfinished single sign-on logins.
+async fn token_cleanup_task(db_pool: Pool<Postgres>) {
+    let now = chrono::Utc::now().naive_utc();
+    let password_reset_query_result = query!(
+        "\
+        DELETE FROM password_reset_token \
+        WHERE used_at IS NOT NULL OR expires_at < $1;\
+        ",
+        now
+    )
+    .execute(&db_pool)
+    .await;
+    let login_challenge_query_result = query!(
+        "\
+        DELETE FROM login_challenge \
+        WHERE expires_at < $1;\
+        ",
+        now
+    )
+    .execute(&db_pool)
+    .await;
+    let api_token_query_result = query!(
+        "\
+        DELETE FROM api_token \
+        WHERE expires_at < $1;\
+        ",
+        now
+    )
+    .execute(&db_pool)
+    .await;
+    let oidc_login_query_result = query!(
         "\
-        DELETE FROM session \
+        DELETE FROM oidc_login \
         WHERE expires_at < $1;\
         ",
-        ready_to_delete
+        now
     )
     .execute(&db_pool)
     .await;
 
-    match query_result {
+    match password_reset_query_result
+        .and(login_challenge_query_result)
+        .and(api_token_query_result)
+        .and(oidc_login_query_result)
+    {
         Err(e) => {
-            log!(Level::Warn, "Failed to cleanup sessions: {}", e);
+            log!(Level::Warn, "Failed to cleanup tokens: {}", e);
         }
         Ok(_) => {
-            log!(Level::Debug, "cleaned up sessions");
+            log!(Level::Debug, "cleaned up tokens");
         }
     }
 }
@@ -35,24 +84,36 @@ async fn session_cleanup_task(expiry_mins: u8, db_pool: Pool<Postgres>) {
 /// Sets up the scheduler for background tasks.
 pub async fn setup_scheduler(
     db_pool: Pool<Postgres>,
+    session_store: Arc<dyn SessionStore>,
     config: Settings,
 ) -> Result<JobScheduler, JobSchedulerError> {
     let db_pool = db_pool.clone();
     let scheduler = JobScheduler::new().await?;
     scheduler.start().await?;
 
-    // delete outdated sessions in database
+    // delete outdated sessions in the session store
     let expiry_mins = config.server.session_expiry_mins;
     // run one second past every <expiry_mins> minute
     let session_cleanup_cron_string = format!("1 0/{} * * * *", expiry_mins);
     let session_cleanup_job = Job::new_async(session_cleanup_cron_string, move |_uuid, _l| {
-        let db_pool = db_pool.clone();
+        let session_store = session_store.clone();
         Box::pin(async move {
-            session_cleanup_task(expiry_mins, db_pool).await;
+            session_cleanup_task(expiry_mins, session_store).await;
         })
     })?;
     scheduler.add(session_cleanup_job).await?;
 
+    // delete used and expired password reset tokens, login challenges and expired API tokens
+    // run one second after 30 minutes past every hour
+    let token_cleanup_cron_string = "1 30 * * * *";
+    let token_cleanup_job = Job::new_async(token_cleanup_cron_string, move |_uuid, _l| {
+        let db_pool = db_pool.clone();
+        Box::pin(async move {
+            token_cleanup_task(db_pool).await;
+        })
+    })?;
+    scheduler.add(token_cleanup_job).await?;
+
     // delete outdated log files
     let log_settings = config.log.clone();
     // run one second after five minutes after midnight
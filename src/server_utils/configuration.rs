@@ -9,6 +9,7 @@ pub struct Settings {
     pub database: DatabaseSettings,
     pub log: LogSettings,
     pub server: ServerSettings,
+    pub mail: MailSettings,
 }
 
 #[derive(Deserialize, Clone)]
@@ -53,10 +54,258 @@ where
 pub struct ServerSettings {
     pub host: String,
     pub port: u16,
-    pub session_secret: Vec<u8>,
+    pub jwt: JwtSettings,
     pub dummy_bcrypt_hash: String,
     #[serde(deserialize_with = "u8_to_expiry_filter")]
     pub session_expiry_mins: u8,
+    pub session_expiry_warning_mins: u8,
+    // default is the session table
+    #[serde(default)]
+    pub session_store: SessionStoreSettings,
+    pub login_lockout: LoginLockoutSettings,
+    pub csrf: CsrfSettings,
+    // default is the password hash of the account only
+    #[serde(default = "default_auth_backends")]
+    pub auth_backends: Vec<AuthBackendSettings>,
+    // default is no rate limiting
+    #[serde(default)]
+    pub rate_limits: Vec<RateLimitSettings>,
+    // default is no rate limiting by IP before the authorisation
+    #[serde(default)]
+    pub ip_rate_limits: Vec<RateLimitSettings>,
+    pub password_reset: PasswordResetSettings,
+    pub totp: TotpSettings,
+    // default is password login only
+    #[serde(default)]
+    pub oidc: Option<OidcSettings>,
+}
+
+/// The `iss` and `aud` claims of session tokens, which are put into new tokens and required
+/// from presented ones. `leeway_secs` allows for clock skew when checking `exp`, `nbf` and `iat`.
+///
+/// New tokens are signed with the `signing_key`, tokens signed with one of the
+/// `verify_only_keys` are still accepted. To rotate, add the current signing key to the
+/// verify-only keys and configure a new signing key with another `kid`. The old key can be
+/// removed once the sessions signed with it have expired.
+///
+/// The `transport` decides how the browser holds the token, see `SessionTransport`.
+#[derive(Deserialize, Clone)]
+pub struct JwtSettings {
+    pub issuer: String,
+    pub audience: String,
+    pub leeway_secs: u64,
+    pub signing_key: JwtKeySettings,
+    #[serde(default)]
+    pub verify_only_keys: Vec<JwtKeySettings>,
+    // default is the bearer token in the session storage
+    #[serde(default)]
+    pub transport: SessionTransport,
+}
+
+/// How session tokens get from the server to the browser and back.
+///
+/// With `bearer`, responses contain the token, the client keeps it in the session storage and
+/// sends it in the `Authorization` header. With `cookie`, the token is only set as
+/// `HttpOnly` cookie, so scripts can't read it and new tabs share the session. `both` does
+/// both and accepts either, to migrate clients. API tokens are always sent as bearer tokens.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionTransport {
+    #[default]
+    Bearer,
+    Cookie,
+    Both,
+}
+
+impl SessionTransport {
+    pub fn uses_bearer(&self) -> bool {
+        *self != SessionTransport::Cookie
+    }
+
+    pub fn uses_cookie(&self) -> bool {
+        *self != SessionTransport::Bearer
+    }
+}
+
+/// A key identified by the `kid` header of the tokens signed with it.
+///
+/// `HS256` keys are the `secret` bytes. `EdDSA` (Ed25519) and `RS256` keys are read from PEM
+/// files, e.g., created by `openssl genpkey -algorithm ed25519 -out jwt.pem` and
+/// `openssl pkey -in jwt.pem -pubout -out jwt.pub.pem`. Their `public_key_path` is required,
+/// the `private_key_path` only for the signing key. The public keys are published at
+/// `/.well-known/jwks.json`, so other services can verify the tokens.
+#[derive(Deserialize, Clone)]
+pub struct JwtKeySettings {
+    pub kid: String,
+    // default is HS256
+    #[serde(default)]
+    pub algorithm: JwtAlgorithm,
+    #[serde(default)]
+    pub secret: Option<Vec<u8>>,
+    #[serde(default)]
+    pub private_key_path: Option<String>,
+    #[serde(default)]
+    pub public_key_path: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum JwtAlgorithm {
+    #[default]
+    HS256,
+    EdDSA,
+    RS256,
+}
+
+/// Where sessions are kept, selected by the `type` field.
+///
+/// The `memory` store is meant for tests and development with a single server, its sessions
+/// are lost on restart.
+#[derive(Deserialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SessionStoreSettings {
+    #[default]
+    Postgres,
+    Memory,
+}
+
+fn default_auth_backends() -> Vec<AuthBackendSettings> {
+    vec![AuthBackendSettings::Database]
+}
+
+/// A backend checking the password of a login, selected by the `type` field.
+///
+/// The backends are asked in the configured order, the first accepting the password wins.
+/// Accounts have to exist locally in any case, the `ldap` backend only takes over the password
+/// check and syncs the name. The `fake` backend knows a fixed list of users and is meant for
+/// the end2end tests only.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum AuthBackendSettings {
+    Database,
+    Ldap {
+        url: String,
+        bind_dn_template: String,
+        name_attribute: String,
+        timeout_secs: u8,
+    },
+    Fake {
+        users: Vec<FakeUserSettings>,
+    },
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FakeUserSettings {
+    pub username: String,
+    pub password: String,
+    pub name: String,
+}
+
+/// Login with an OpenID Connect provider by the authorization code flow with PKCE.
+///
+/// `redirect_url` has to point to `<the public url of the app>/oidc-callback`, a started login
+/// waits `login_expiry_mins` minutes for the provider's callback.
+///
+/// After the first login, an account is linked to the provider's `sub` claim. Up to then, the
+/// account whose username equals the `link_claim` of the ID token is linked, if the provider
+/// has verified the claim by a `<link_claim>_verified` claim, e.g., `email` by
+/// `email_verified`. Admin accounts are never linked this way. Without such an account, a new
+/// one is created if `provision_accounts` is set.
+#[derive(Deserialize, Clone)]
+pub struct OidcSettings {
+    pub discovery_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+    pub link_claim: String,
+    pub provision_accounts: bool,
+    pub login_expiry_mins: u8,
+}
+
+/// `issuer` is shown in authenticator apps, a started login waits `challenge_expiry_mins`
+/// minutes for the second factor.
+#[derive(Deserialize, Clone)]
+pub struct TotpSettings {
+    pub issuer: String,
+    pub challenge_expiry_mins: u8,
+}
+
+/// Reset links point to `<link_base_url>/reset-password` and are valid for `token_expiry_mins`.
+#[derive(Deserialize, Clone)]
+pub struct PasswordResetSettings {
+    pub link_base_url: String,
+    pub token_expiry_mins: u16,
+}
+
+/// Server fns are only accepted from pages of the `allowed_origin`, the scheme, host and port of
+/// the public url of the app, e.g., `https://example.com`.
+#[derive(Deserialize, Clone)]
+pub struct CsrfSettings {
+    pub allowed_origin: String,
+}
+
+/// The user is warned `mins` minutes before the session expires, from
+/// `session_expiry_warning_mins`.
+#[derive(Clone, Copy)]
+pub struct SessionExpiryWarningSettings {
+    pub mins: u8,
+}
+
+/// Temporarily locks an account after `max_failed_attempts` failed logins
+/// within `window_mins` minutes for `lockout_mins` minutes.
+#[derive(Deserialize, Clone)]
+pub struct LoginLockoutSettings {
+    pub max_failed_attempts: u8,
+    pub window_mins: u16,
+    pub lockout_mins: u16,
+}
+
+/// A token bucket limit for all server fns whose path starts with `path_prefix`.
+///
+/// Up to `burst` requests are allowed at once, refilled by `per_minute` requests per minute.
+#[derive(Deserialize, Clone)]
+pub struct RateLimitSettings {
+    pub path_prefix: String,
+    #[serde(deserialize_with = "u32_to_non_zero_filter")]
+    pub burst: u32,
+    #[serde(deserialize_with = "u32_to_non_zero_filter")]
+    pub per_minute: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MailSettings {
+    pub from: String,
+    pub transport: MailTransportSettings,
+}
+
+/// How mails are delivered, selected by the `type` field.
+///
+/// The `directory` transport just writes each mail to a file in `path`,
+/// which is meant for development and tests.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MailTransportSettings {
+    Smtp {
+        host: String,
+        port: u16,
+        username: String,
+        password: String,
+    },
+    Directory {
+        path: String,
+    },
+}
+
+/// Make sure that a rate limit allows any requests at all.
+fn u32_to_non_zero_filter<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = u32::deserialize(deserializer)?;
+    if value == 0 {
+        log!(log::Level::Error, "rate limits must not be zero");
+        return Err(Error::custom("rate limits must not be zero"));
+    }
+    Ok(value)
 }
 
 /// Make sure that the session expiry time is a divider of 60.
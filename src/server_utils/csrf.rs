@@ -0,0 +1,209 @@
+use crate::api::auth_context::AuthContext;
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::server_utils::configuration::CsrfSettings;
+use crate::server_utils::public_endpoints::PublicEndpoints;
+use crate::server_utils::token::hash_token;
+use crate::utils::{CSRF_COOKIE, CSRF_FIELD, CSRF_HEADER};
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{CONTENT_TYPE, ORIGIN, REFERER};
+use actix_web::web::{Bytes, Query};
+use actix_web::{Error, HttpMessage, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use log::{log, Level};
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+
+/// The hash of the CSRF token of the request's session, inserted by the `Authorisation`
+/// middleware.
+pub struct CsrfTokenHash(pub String);
+
+/// Creates the cookie holding the CSRF `token` of a new session.
+///
+/// It isn't `HttpOnly`, as the client sends the token as header. A cross-site page can't read
+/// it, so it can't send the token either. It's `Secure`, if the app is served by https.
+pub fn csrf_cookie(token: &str, settings: &CsrfSettings) -> Cookie<'static> {
+    Cookie::build(CSRF_COOKIE, token.to_string())
+        .path("/")
+        .secure(settings.allowed_origin.starts_with("https://"))
+        .same_site(SameSite::Strict)
+        .finish()
+}
+
+/// Creates the cookie making the browser delete the CSRF cookie.
+pub fn csrf_removal_cookie() -> Cookie<'static> {
+    let mut cookie = Cookie::build(CSRF_COOKIE, "")
+        .path("/")
+        .same_site(SameSite::Strict)
+        .finish();
+    cookie.make_removal();
+    cookie
+}
+
+/// This wraps the CSRF protection for leptos server fns.
+///
+/// If the browser sends an `Origin` or `Referer` header, it has to be the `allowed_origin`.
+/// Server fns of a session additionally need the session's CSRF token in the `CSRF_HEADER`
+/// or, for form posts, in the `CSRF_FIELD`. Public server fns and requests with API tokens
+/// don't, as they don't rely on credentials the browser sends by itself.
+///
+/// The token is checked against the session, so it has to be wrapped inside `Authorisation`.
+pub struct Csrf {
+    allowed_origin: Rc<String>,
+    public_endpoints: Rc<PublicEndpoints>,
+}
+
+impl Csrf {
+    pub fn new(allowed_origin: String, public_endpoints: PublicEndpoints) -> Self {
+        Self {
+            allowed_origin: Rc::new(allowed_origin),
+            public_endpoints: Rc::new(public_endpoints),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Csrf
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CsrfMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfMiddleware {
+            service: service.into(),
+            allowed_origin: self.allowed_origin.clone(),
+            public_endpoints: self.public_endpoints.clone(),
+        }))
+    }
+}
+
+pub struct CsrfMiddleware<S> {
+    service: Rc<S>,
+    allowed_origin: Rc<String>,
+    public_endpoints: Rc<PublicEndpoints>,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = self.service.clone();
+        let has_allowed_origin = has_allowed_origin(&req, &self.allowed_origin);
+        let needs_token = !self.public_endpoints.contains(req.path())
+            && !req
+                .extensions()
+                .get::<AuthContext>()
+                .is_some_and(|auth| auth.via_api_token);
+
+        Box::pin(async move {
+            let mut req = req;
+            let is_valid = has_allowed_origin && (!needs_token || has_valid_token(&mut req).await);
+            if !is_valid {
+                log!(
+                    Level::Warn,
+                    "CSRF check failed for {} from {:?}",
+                    req.path(),
+                    req.peer_addr()
+                );
+                let new_body = ApiResponse {
+                    expires_at: 0,
+                    token: "".to_string(),
+                    error: Some(ApiError::Forbidden),
+                    data: (),
+                };
+                let new_http_response = HttpResponse::Ok().json(new_body);
+                let new_service_response =
+                    ServiceResponse::new(req.request().clone(), new_http_response);
+                return Ok(new_service_response.map_into_right_body());
+            }
+            let res = srv.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+/// Whether the `Origin` header, or without it the `Referer` header, is the `allowed_origin`.
+///
+/// Requests without both headers aren't sent by a cross-site page of a browser.
+fn has_allowed_origin(req: &ServiceRequest, allowed_origin: &str) -> bool {
+    if let Some(origin) = req.headers().get(ORIGIN) {
+        return origin.to_str().is_ok_and(|origin| origin == allowed_origin);
+    }
+    if let Some(referer) = req.headers().get(REFERER) {
+        return referer.to_str().is_ok_and(|referer| {
+            referer
+                .strip_prefix(allowed_origin)
+                .is_some_and(|path| path.is_empty() || path.starts_with('/'))
+        });
+    }
+    true
+}
+
+/// Whether the request contains the CSRF token of its session.
+async fn has_valid_token(req: &mut ServiceRequest) -> bool {
+    let token_hash = match req.extensions().get::<CsrfTokenHash>() {
+        None => {
+            log!(
+                Level::Warn,
+                "No CSRF token of the session for {}",
+                req.path()
+            );
+            return false;
+        }
+        Some(token_hash) => token_hash.0.clone(),
+    };
+    let header_token = req
+        .headers()
+        .get(CSRF_HEADER)
+        .map(|header_value| header_value.to_str().ok().map(|token| token.to_string()));
+    let token = match header_token {
+        Some(token) => token,
+        None => get_form_token(req).await,
+    };
+
+    token.is_some_and(|token| hash_token(&token) == token_hash)
+}
+
+/// Reads the `CSRF_FIELD` of a form post and puts the body back for the server fn.
+async fn get_form_token(req: &mut ServiceRequest) -> Option<String> {
+    let is_form = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/x-www-form-urlencoded"));
+    if !is_form {
+        return None;
+    }
+    let body = match req.extract::<Bytes>().await {
+        Err(err) => {
+            log!(Level::Warn, "Couldn't read form body: {}", err);
+            return None;
+        }
+        Ok(body) => body,
+    };
+    let token = std::str::from_utf8(&body)
+        .ok()
+        .and_then(|form| Query::<HashMap<String, String>>::from_query(form).ok())
+        .and_then(|fields| fields.into_inner().remove(CSRF_FIELD));
+    req.set_payload(Payload::from(body));
+
+    token
+}
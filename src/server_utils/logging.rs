@@ -97,15 +97,12 @@ impl Logger {
         let mut files_in_dir_map: HashMap<String, Option<DateTime<Utc>>> = HashMap::new();
         // collect readable dir entries
         for dir_entry in files_in_dir_vec {
-            match dir_entry.file_name().to_str() {
-                Some(file_name) => {
-                    files_in_dir_map.insert(file_name.to_string(), None);
-                }
-                None => {}
+            if let Some(file_name) = dir_entry.file_name().to_str() {
+                files_in_dir_map.insert(file_name.to_string(), None);
             }
         }
         // collect log files
-        for (file_name, _) in &files_in_dir_map.clone() {
+        for file_name in files_in_dir_map.clone().keys() {
             if log_file_reg_ex.is_match(file_name.as_str()) {
                 // this `unwrap` is approved because we know we have a match
                 let capture = log_file_reg_ex.captures(file_name.as_str()).unwrap();
@@ -254,7 +251,7 @@ impl Logger {
             sender: log_sender,
             env: env::var("LSF_ENV").unwrap_or_else(|_| "DEV".to_string()),
         });
-        let _ = tokio::task::spawn(async move {
+        tokio::task::spawn(async move {
             logger.logging_task(log_receiver).await;
         });
 
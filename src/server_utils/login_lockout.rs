@@ -0,0 +1,80 @@
+use crate::server_utils::configuration::LoginLockoutSettings;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use sqlx::types::Uuid;
+use sqlx::{query, Pool, Postgres};
+
+/// Records a failed login for the given account and locks it, if there were too many.
+///
+/// Failed logins are counted within a window starting with the first failure. If the window
+/// has passed, counting starts again. Reaching `max_failed_attempts` within the window locks
+/// the account for `lockout_mins` minutes.
+///
+/// # Returns
+/// - `Ok(Some(NaiveDateTime))`: The account has just been locked until the returned time.
+/// - `Ok(None)`: The failed login has been recorded, but the account is not locked.
+pub async fn record_failed_login(
+    db_pool: &Pool<Postgres>,
+    account_id: Uuid,
+    settings: &LoginLockoutSettings,
+) -> Result<Option<NaiveDateTime>, sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let window_start = now - TimeDelta::minutes(settings.window_mins as i64);
+    let locked_until = now + TimeDelta::minutes(settings.lockout_mins as i64);
+    // counting and locking in one statement, the row lock makes concurrent failures wait
+    let account_row = query!(
+        "\
+            WITH next AS ( \
+                SELECT id, \
+                    CASE \
+                        WHEN failed_login_window_start > $2 THEN failed_logins + 1 \
+                        ELSE 1 \
+                    END AS attempts, \
+                    CASE \
+                        WHEN failed_login_window_start > $2 THEN failed_login_window_start \
+                        ELSE $3 \
+                    END AS window_start \
+                FROM account \
+                WHERE id = $1 \
+                FOR UPDATE \
+            ) \
+            UPDATE account \
+            SET failed_logins = CASE WHEN next.attempts >= $4 THEN 0 ELSE next.attempts END, \
+                failed_login_window_start = CASE \
+                    WHEN next.attempts >= $4 THEN NULL \
+                    ELSE next.window_start \
+                END, \
+                locked_until = CASE WHEN next.attempts >= $4 THEN $5 ELSE account.locked_until END \
+            FROM next \
+            WHERE account.id = next.id \
+            RETURNING account.locked_until, next.attempts >= $4 AS \"just_locked!\"\
+        ",
+        account_id,
+        window_start,
+        now,
+        settings.max_failed_attempts as i32,
+        locked_until
+    )
+    .fetch_one(db_pool)
+    .await?;
+
+    Ok(account_row.locked_until.filter(|_| account_row.just_locked))
+}
+
+/// Forgets about failed logins and an expired lock after a successful login.
+pub async fn reset_failed_logins(
+    db_pool: &Pool<Postgres>,
+    account_id: Uuid,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "\
+            UPDATE account \
+            SET failed_logins = 0, failed_login_window_start = NULL, locked_until = NULL \
+            WHERE id = $1\
+        ",
+        account_id
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use crate::server_utils::configuration::{MailSettings, MailTransportSettings};
+use chrono::Utc;
+use futures_util::future::BoxFuture;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A plain text mail to a single recipient.
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct MailError(String);
+
+impl Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Sends mails, e.g., password reset links.
+///
+/// Server fns get the configured implementation from context as `Data<dyn Mailer>`.
+pub trait Mailer: Send + Sync {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), MailError>>;
+}
+
+/// Creates the mailer for the configured transport.
+pub fn new_mailer(settings: &MailSettings) -> Result<Arc<dyn Mailer>, MailError> {
+    match &settings.transport {
+        MailTransportSettings::Smtp {
+            host,
+            port,
+            username,
+            password,
+        } => Ok(Arc::new(SmtpMailer::new(
+            &settings.from,
+            host,
+            *port,
+            username,
+            password,
+        )?)),
+        MailTransportSettings::Directory { path } => {
+            Ok(Arc::new(DirectoryMailer::new(&settings.from, path)))
+        }
+    }
+}
+
+/// Sends mails via an SMTP relay using STARTTLS.
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        from: &str,
+        host: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+    ) -> Result<Self, MailError> {
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|err| MailError(format!("Invalid sender address: {}", err)))?;
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|err| MailError(format!("Invalid SMTP relay: {}", err)))?
+            .port(port)
+            .credentials(Credentials::new(username.to_string(), password.to_string()))
+            .build();
+
+        Ok(Self { from, transport })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), MailError>> {
+        Box::pin(async move {
+            let to = mail
+                .to
+                .parse::<Mailbox>()
+                .map_err(|err| MailError(format!("Invalid recipient address: {}", err)))?;
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(to)
+                .subject(mail.subject)
+                .header(ContentType::TEXT_PLAIN)
+                .body(mail.body)
+                .map_err(|err| MailError(format!("Couldn't build mail: {}", err)))?;
+            self.transport
+                .send(message)
+                .await
+                .map_err(|err| MailError(format!("Couldn't send mail: {}", err)))?;
+
+            Ok(())
+        })
+    }
+}
+
+/// Writes every mail to its own file in a directory instead of sending it.
+///
+/// Meant for development and the end2end tests, which read the mails from there.
+pub struct DirectoryMailer {
+    from: String,
+    path: PathBuf,
+}
+
+impl DirectoryMailer {
+    pub fn new(from: &str, path: &str) -> Self {
+        Self {
+            from: from.to_string(),
+            path: PathBuf::from(path),
+        }
+    }
+}
+
+impl Mailer for DirectoryMailer {
+    fn send(&self, mail: Mail) -> BoxFuture<'_, Result<(), MailError>> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.path)
+                .await
+                .map_err(|err| MailError(format!("Couldn't create mail directory: {}", err)))?;
+            // the timestamp keeps the files in order, the random part apart
+            let file_name = format!(
+                "{}_{:08x}.eml",
+                Utc::now().format("%Y%m%d%H%M%S%3f"),
+                getrandom::u32().unwrap_or_default()
+            );
+            let content = format!(
+                "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+                self.from, mail.to, mail.subject, mail.body
+            );
+            tokio::fs::write(self.path.join(file_name), content)
+                .await
+                .map_err(|err| MailError(format!("Couldn't write mail: {}", err)))?;
+
+            Ok(())
+        })
+    }
+}
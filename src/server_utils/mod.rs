@@ -1,4 +1,16 @@
+pub mod auth_backend;
 pub mod authorization;
 pub mod background_task;
 pub mod configuration;
+pub mod csrf;
 pub mod logging;
+pub mod login_lockout;
+pub mod mailer;
+pub mod oidc;
+pub mod password_reset;
+pub mod public_endpoints;
+pub mod rate_limit;
+pub mod session_cookie;
+pub mod session_store;
+pub mod token;
+pub mod totp;
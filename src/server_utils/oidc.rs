@@ -0,0 +1,217 @@
+use crate::server_utils::configuration::OidcSettings;
+use actix_web::cookie::time::Duration;
+use actix_web::cookie::{Cookie, SameSite};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::fmt::Display;
+
+/// The name of the cookie tying a started login to the browser it was started in.
+pub const OIDC_STATE_COOKIE: &str = "lsf_oidc_state";
+
+/// The endpoints of a provider as published by its discovery document.
+#[derive(Deserialize, Clone)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// The validated claims of an ID token.
+pub struct IdTokenClaims {
+    pub subject: String,
+    claims: Map<String, Value>,
+}
+
+impl IdTokenClaims {
+    /// Gets a string claim, e.g., `name` or `email`.
+    pub fn get(&self, claim: &str) -> Option<String> {
+        self.claims
+            .get(claim)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+    }
+
+    /// Whether the provider vouches for a claim by a `<claim>_verified` claim being true,
+    /// e.g., `email_verified`. Claims without one, like `preferred_username`, aren't verified.
+    pub fn is_verified(&self, claim: &str) -> bool {
+        self.claims
+            .get(&format!("{}_verified", claim))
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+    }
+}
+
+/// Creates the cookie holding the `state` of a started login, which the callback has to match.
+///
+/// Otherwise, a victim could be sent the callback url of the attacker's login and would be
+/// logged in to the attacker's account. It's `SameSite=Lax`, as the provider redirects back
+/// cross-site, and `Secure`, if the app is served by https.
+pub fn oidc_state_cookie(state: &str, settings: &OidcSettings) -> Cookie<'static> {
+    Cookie::build(OIDC_STATE_COOKIE, state.to_string())
+        .path("/")
+        .secure(settings.redirect_url.starts_with("https://"))
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(Duration::minutes(settings.login_expiry_mins as i64))
+        .finish()
+}
+
+/// Creates the cookie making the browser delete the state cookie.
+pub fn oidc_state_removal_cookie() -> Cookie<'static> {
+    let mut cookie = Cookie::build(OIDC_STATE_COOKIE, "")
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .finish();
+    cookie.make_removal();
+    cookie
+}
+
+#[derive(Debug)]
+pub struct OidcError(String);
+
+impl Display for OidcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<reqwest::Error> for OidcError {
+    fn from(err: reqwest::Error) -> Self {
+        OidcError(format!("Request to provider failed: {}", err))
+    }
+}
+
+/// Fetches the endpoints of the configured provider.
+///
+/// It's fetched for each login, as logins are rare and providers may change their endpoints.
+pub async fn discover(settings: &OidcSettings) -> Result<ProviderMetadata, OidcError> {
+    Ok(reqwest::get(&settings.discovery_url)
+        .await?
+        .error_for_status()?
+        .json::<ProviderMetadata>()
+        .await?)
+}
+
+/// The PKCE challenge sent with the authorization request for the secret `code_verifier`.
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// The url to send the browser to for the login at the provider.
+pub fn authorization_url(
+    metadata: &ProviderMetadata,
+    settings: &OidcSettings,
+    state: &str,
+    code_verifier: &str,
+    nonce: &str,
+) -> Result<String, OidcError> {
+    let url = Url::parse_with_params(
+        &metadata.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", settings.client_id.as_str()),
+            ("redirect_uri", settings.redirect_url.as_str()),
+            ("scope", "openid profile email"),
+            ("state", state),
+            ("nonce", nonce),
+            ("code_challenge", code_challenge(code_verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|err| OidcError(format!("Invalid authorization endpoint: {}", err)))?;
+
+    Ok(url.to_string())
+}
+
+/// Exchanges the code of the callback for the ID token and validates it.
+pub async fn exchange_code(
+    metadata: &ProviderMetadata,
+    settings: &OidcSettings,
+    code: &str,
+    code_verifier: &str,
+    nonce: &str,
+) -> Result<IdTokenClaims, OidcError> {
+    let client = reqwest::Client::new();
+    let token_response = client
+        .post(&metadata.token_endpoint)
+        .basic_auth(&settings.client_id, Some(&settings.client_secret))
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", settings.redirect_url.as_str()),
+            ("code_verifier", code_verifier),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<TokenResponse>()
+        .await?;
+
+    validate_id_token(metadata, settings, &token_response.id_token, nonce).await
+}
+
+/// Checks the signature with the provider's published keys, the issuer, the audience, the
+/// expiry and the nonce of the login.
+async fn validate_id_token(
+    metadata: &ProviderMetadata,
+    settings: &OidcSettings,
+    id_token: &str,
+    nonce: &str,
+) -> Result<IdTokenClaims, OidcError> {
+    let header = decode_header(id_token)
+        .map_err(|err| OidcError(format!("Invalid ID token header: {}", err)))?;
+    // a shared secret algorithm would make the public keys the secret
+    if matches!(
+        header.alg,
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+    ) {
+        return Err(OidcError(format!(
+            "ID token algorithm not allowed: {:?}",
+            header.alg
+        )));
+    }
+    let jwks = reqwest::get(&metadata.jwks_uri)
+        .await?
+        .error_for_status()?
+        .json::<JwkSet>()
+        .await?;
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    }
+    .ok_or_else(|| OidcError("No matching key for the ID token".to_string()))?;
+    let key = DecodingKey::from_jwk(jwk)
+        .map_err(|err| OidcError(format!("Invalid provider key: {}", err)))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_issuer(&[&metadata.issuer]);
+    validation.set_audience(&[&settings.client_id]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    let claims = decode::<Map<String, Value>>(id_token, &key, &validation)
+        .map_err(|err| OidcError(format!("Invalid ID token: {}", err)))?
+        .claims;
+
+    if claims.get("nonce").and_then(|value| value.as_str()) != Some(nonce) {
+        return Err(OidcError("ID token nonce doesn't match".to_string()));
+    }
+    let subject = claims
+        .get("sub")
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| OidcError("ID token without subject".to_string()))?
+        .to_string();
+
+    Ok(IdTokenClaims { subject, claims })
+}
@@ -0,0 +1,36 @@
+use crate::model::language::Language;
+
+/// Returns the subject and body of the reset mail in the user's preferred language.
+pub fn reset_mail_text(
+    lang: &Language,
+    name: &str,
+    link: &str,
+    expiry_mins: u16,
+) -> (String, String) {
+    match lang {
+        Language::De => (
+            "Passwort zurücksetzen".to_string(),
+            format!(
+                "Hallo {},\n\n\
+                jemand hat das Zurücksetzen Ihres Passworts angefordert. \
+                Über den folgenden Link können Sie ein neues Passwort vergeben:\n\n\
+                {}\n\n\
+                Der Link ist {} Minuten gültig und kann nur einmal verwendet werden. \
+                Falls Sie das nicht angefordert haben, können Sie diese E-Mail ignorieren.\n",
+                name, link, expiry_mins
+            ),
+        ),
+        Language::En => (
+            "Reset your password".to_string(),
+            format!(
+                "Hello {},\n\n\
+                someone requested to reset your password. \
+                You can choose a new password using the following link:\n\n\
+                {}\n\n\
+                The link is valid for {} minutes and can only be used once. \
+                If you didn't request this, you can ignore this mail.\n",
+                name, link, expiry_mins
+            ),
+        ),
+    }
+}
@@ -0,0 +1,51 @@
+use crate::layout::server_message::GetMessage;
+use crate::layout::session_expiry::GetSessionExpiryWarningMins;
+use crate::pages::forgot_password::RequestPasswordReset;
+use crate::pages::login::{Login, VerifyTotp};
+use crate::pages::oidc_login::{CompleteOidcLogin, GetOidcEnabled, StartOidcLogin};
+use crate::pages::reset_password::ConfirmPasswordReset;
+use log::{log, Level};
+use server_fn::ServerFn;
+use std::collections::BTreeSet;
+
+/// The server fns callable without a session, identified by their exact paths.
+///
+/// Paths of server fns end with a hash, so they are taken from `ServerFn::PATH` instead of
+/// being written down. Every path not registered needs authorization.
+#[derive(Clone, Default)]
+pub struct PublicEndpoints {
+    paths: BTreeSet<&'static str>,
+}
+
+impl PublicEndpoints {
+    pub fn register<T: ServerFn>(mut self) -> Self {
+        self.paths.insert(T::PATH);
+        self
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+
+    pub fn log(&self) {
+        for path in self.paths.iter() {
+            log!(Level::Info, "Public endpoint: {}", path);
+        }
+    }
+}
+
+/// The registry of the public server fns of this app.
+///
+/// A new server fn is only public, if it is added here.
+pub fn public_endpoints() -> PublicEndpoints {
+    PublicEndpoints::default()
+        .register::<Login>()
+        .register::<VerifyTotp>()
+        .register::<GetMessage>()
+        .register::<GetSessionExpiryWarningMins>()
+        .register::<RequestPasswordReset>()
+        .register::<ConfirmPasswordReset>()
+        .register::<GetOidcEnabled>()
+        .register::<StartOidcLogin>()
+        .register::<CompleteOidcLogin>()
+}
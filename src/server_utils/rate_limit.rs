@@ -0,0 +1,299 @@
+use crate::api::auth_context::AuthContext;
+use crate::api::error::ApiError;
+use crate::api::response::ApiResponse;
+use crate::server_utils::configuration::RateLimitSettings;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{Error, HttpMessage, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use log::{log, Level};
+use sqlx::types::Uuid;
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Above this number of buckets, full ones are dropped, as they behave like new ones.
+const MAX_BUCKETS: usize = 10_000;
+
+/// Who a request is counted for.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum RateLimitKey {
+    Ip(Option<IpAddr>),
+    Account(Uuid),
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn full(settings: &RateLimitSettings, now: Instant) -> Self {
+        Self {
+            tokens: settings.burst as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, settings: &RateLimitSettings, now: Instant) {
+        let elapsed_secs = now.duration_since(self.last_refill).as_secs_f64();
+        let refilled = self.tokens + elapsed_secs * settings.per_minute as f64 / 60.0;
+        self.tokens = refilled.min(settings.burst as f64);
+        self.last_refill = now;
+    }
+
+    fn is_full(&mut self, settings: &RateLimitSettings, now: Instant) -> bool {
+        self.refill(settings, now);
+        self.tokens >= settings.burst as f64
+    }
+
+    /// Takes a token or returns the seconds until one is available.
+    fn try_take(&mut self, settings: &RateLimitSettings, now: Instant) -> Result<(), u64> {
+        self.refill(settings, now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(());
+        }
+        let missing_secs = (1.0 - self.tokens) * 60.0 / settings.per_minute as f64;
+        Err(missing_secs.ceil() as u64)
+    }
+}
+
+struct RateLimiter {
+    rules: Vec<RateLimitSettings>,
+    buckets: Mutex<HashMap<(usize, RateLimitKey), TokenBucket>>,
+}
+
+impl RateLimiter {
+    fn new(rules: Vec<RateLimitSettings>) -> Self {
+        Self {
+            rules,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a request to `path` and returns the seconds to wait if it is over the limit.
+    ///
+    /// The rule with the longest matching path prefix applies. Paths without a rule are
+    /// not limited.
+    fn check(&self, path: &str, key: RateLimitKey) -> Option<u64> {
+        let (rule_index, rule) = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| path.starts_with(rule.path_prefix.as_str()))
+            .max_by_key(|(_, rule)| rule.path_prefix.len())?;
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() > MAX_BUCKETS {
+            buckets.retain(|(index, _), bucket| !bucket.is_full(&self.rules[*index], now));
+        }
+        buckets
+            .entry((rule_index, key))
+            .or_insert_with(|| TokenBucket::full(rule, now))
+            .try_take(rule, now)
+            .err()
+    }
+}
+
+/// This wraps rate limiting for leptos server fns using token buckets.
+///
+/// Created by `new`, anonymous requests are counted by peer IP, authenticated ones by account.
+/// So it has to be wrapped inside `Authorisation`. Created by `by_ip`, all requests are counted
+/// by peer IP, so it can be wrapped around `Authorisation` to stop floods before they reach
+/// the database or extend a session.
+#[derive(Clone)]
+pub struct RateLimit {
+    limiter: Arc<RateLimiter>,
+    by_account: bool,
+}
+
+impl RateLimit {
+    /// Creates the rate limiting by account, whose buckets are shared by all clones,
+    /// e.g., by the app instances of all workers.
+    pub fn new(rules: Vec<RateLimitSettings>) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(rules)),
+            by_account: true,
+        }
+    }
+
+    /// Creates the rate limiting by peer IP, whose buckets are shared by all clones.
+    pub fn by_ip(rules: Vec<RateLimitSettings>) -> Self {
+        Self {
+            limiter: Arc::new(RateLimiter::new(rules)),
+            by_account: false,
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: service.into(),
+            limiter: self.limiter.clone(),
+            by_account: self.by_account,
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limiter: Arc<RateLimiter>,
+    by_account: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static + MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let srv = self.service.clone();
+        // the auth context has been set by `Authorisation` for authenticated requests
+        let account_id = req
+            .extensions()
+            .get::<AuthContext>()
+            .map(|auth| auth.account_id)
+            .filter(|_| self.by_account);
+        let key = match account_id {
+            Some(account_id) => RateLimitKey::Account(account_id),
+            None => RateLimitKey::Ip(req.peer_addr().map(|addr| addr.ip())),
+        };
+        let retry_after = self.limiter.check(req.path(), key);
+
+        Box::pin(async move {
+            if let Some(retry_after) = retry_after {
+                log!(
+                    Level::Info,
+                    "Rate limited request to {} from {:?}",
+                    req.path(),
+                    req.peer_addr()
+                );
+                let new_body = ApiResponse {
+                    expires_at: 0,
+                    token: "".to_string(),
+                    error: Some(ApiError::RateLimited(retry_after)),
+                    data: (),
+                };
+                let new_http_response = HttpResponse::Ok().json(new_body);
+                let new_service_response =
+                    ServiceResponse::new(req.request().clone(), new_http_response);
+                return Ok(new_service_response.map_into_right_body());
+            }
+            let res = srv.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn settings(path_prefix: &str, burst: u32, per_minute: u32) -> RateLimitSettings {
+        RateLimitSettings {
+            path_prefix: path_prefix.to_string(),
+            burst,
+            per_minute,
+        }
+    }
+
+    #[test]
+    fn bucket_allows_the_burst_at_once() {
+        let settings = settings("/api", 3, 60);
+        let now = Instant::now();
+        let mut bucket = TokenBucket::full(&settings, now);
+
+        for _ in 0..3 {
+            assert_eq!(bucket.try_take(&settings, now), Ok(()));
+        }
+        assert_eq!(bucket.try_take(&settings, now), Err(1));
+    }
+
+    #[test]
+    fn bucket_refills_per_minute() {
+        let settings = settings("/api", 2, 6);
+        let now = Instant::now();
+        let mut bucket = TokenBucket::full(&settings, now);
+        bucket.try_take(&settings, now).unwrap();
+        bucket.try_take(&settings, now).unwrap();
+
+        // one token every ten seconds
+        assert_eq!(
+            bucket.try_take(&settings, now + Duration::from_secs(4)),
+            Err(6)
+        );
+        assert_eq!(
+            bucket.try_take(&settings, now + Duration::from_secs(10)),
+            Ok(())
+        );
+        assert!(!bucket.is_full(&settings, now + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn bucket_refills_up_to_the_burst() {
+        let settings = settings("/api", 2, 60);
+        let now = Instant::now();
+        let mut bucket = TokenBucket::full(&settings, now);
+        bucket.try_take(&settings, now).unwrap();
+
+        let later = now + Duration::from_secs(600);
+        assert!(bucket.is_full(&settings, later));
+        assert_eq!(bucket.try_take(&settings, later), Ok(()));
+        assert_eq!(bucket.try_take(&settings, later), Ok(()));
+        assert!(bucket.try_take(&settings, later).is_err());
+    }
+
+    #[test]
+    fn limiter_applies_the_longest_matching_prefix() {
+        let limiter = RateLimiter::new(vec![
+            settings("/api", 100, 60),
+            settings("/api/login", 1, 1),
+        ]);
+        let key = RateLimitKey::Ip(None);
+
+        assert_eq!(limiter.check("/api/login", key), None);
+        assert!(limiter.check("/api/login", key).is_some());
+        assert_eq!(limiter.check("/api/get_user", key), None);
+        assert_eq!(limiter.check("/other", key), None);
+    }
+
+    #[test]
+    fn limiter_counts_keys_separately() {
+        let limiter = RateLimiter::new(vec![settings("/api", 1, 1)]);
+        let ip = RateLimitKey::Ip(Some(IpAddr::from([127, 0, 0, 1])));
+        let other_ip = RateLimitKey::Ip(Some(IpAddr::from([127, 0, 0, 2])));
+        let account = RateLimitKey::Account(Uuid::nil());
+
+        assert_eq!(limiter.check("/api/login", ip), None);
+        assert!(limiter.check("/api/login", ip).is_some());
+        assert_eq!(limiter.check("/api/login", other_ip), None);
+        assert_eq!(limiter.check("/api/login", account), None);
+    }
+}
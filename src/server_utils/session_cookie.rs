@@ -0,0 +1,56 @@
+use actix_web::cookie::time::Duration;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::http::header::{HeaderValue, SET_COOKIE};
+use chrono::Utc;
+use leptos::prelude::use_context;
+use leptos_actix::ResponseOptions;
+use log::{log, Level};
+
+/// The name of the cookie holding the session token, if the transport uses cookies.
+pub const SESSION_COOKIE: &str = "lsf_session";
+
+/// Creates the cookie holding `token`, which expires with the session at `expires_at`.
+///
+/// The path is `/`, so later the pages can be rendered for the session, too.
+pub fn session_cookie(token: &str, expires_at: i64) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, token.to_string())
+        .path("/")
+        .secure(true)
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .max_age(Duration::seconds(expires_at - Utc::now().timestamp()))
+        .finish()
+}
+
+/// Creates the cookie making the browser delete the session cookie.
+pub fn removal_cookie() -> Cookie<'static> {
+    let mut cookie = Cookie::build(SESSION_COOKIE, "")
+        .path("/")
+        .secure(true)
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .finish();
+    cookie.make_removal();
+    cookie
+}
+
+/// Adds `cookie` to the response of the current server fn.
+///
+/// The `Authorisation` middleware doesn't refresh the session cookie of a response setting it.
+pub fn set_cookie(cookie: Cookie<'static>) {
+    let response_options = match use_context::<ResponseOptions>() {
+        None => {
+            log!(
+                Level::Error,
+                "No response options to set the cookie {}",
+                cookie.name()
+            );
+            return;
+        }
+        Some(response_options) => response_options,
+    };
+    match HeaderValue::from_str(&cookie.to_string()) {
+        Err(err) => log!(Level::Error, "Invalid cookie {}: {}", cookie.name(), err),
+        Ok(header_value) => response_options.append_header(SET_COOKIE, header_value),
+    }
+}
@@ -0,0 +1,474 @@
+use crate::server_utils::configuration::SessionStoreSettings;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use futures_util::future::BoxFuture;
+use log::{log, Level};
+use sqlx::types::uuid::Builder;
+use sqlx::types::Uuid;
+use sqlx::{query, query_as, Pool, Postgres};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+/// A login session, the JWTs are issued for its `id`.
+#[derive(Clone)]
+pub struct Session {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: NaiveDateTime,
+    /// The expiry in UTC, extended whenever the session is used.
+    pub expires_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    /// The admin who started the session to impersonate the account, if any.
+    pub impersonator_id: Option<Uuid>,
+    pub csrf_token_hash: String,
+}
+
+/// The data of a session to be created, the store sets its id and expiry.
+pub struct NewSession {
+    pub account_id: Uuid,
+    /// Helps the user to recognize the session on the sessions page.
+    pub user_agent: Option<String>,
+    pub impersonator_id: Option<Uuid>,
+    pub csrf_token_hash: String,
+}
+
+#[derive(Debug)]
+pub struct SessionStoreError(String);
+
+impl Display for SessionStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Keeps the login sessions.
+///
+/// The middleware, login and cleanup job get the configured implementation as
+/// `Data<dyn SessionStore>`.
+pub trait SessionStore: Send + Sync {
+    /// Creates a session, which expires after the configured session expiry.
+    fn create(&self, session: NewSession) -> BoxFuture<'_, Result<Session, SessionStoreError>>;
+
+    fn find(&self, id: Uuid) -> BoxFuture<'_, Result<Option<Session>, SessionStoreError>>;
+
+    /// Returns the unexpired sessions of `account_id`, the most recently used first.
+    fn list(&self, account_id: Uuid) -> BoxFuture<'_, Result<Vec<Session>, SessionStoreError>>;
+
+    /// Extends a used session by the session expiry and returns its new expiry.
+    ///
+    /// # Returns
+    /// - `Ok(None)`: If there is no such session, e.g., it has been revoked meanwhile.
+    fn touch(&self, id: Uuid) -> BoxFuture<'_, Result<Option<NaiveDateTime>, SessionStoreError>>;
+
+    /// Deletes the session `id` of `account_id` and returns whether there was one.
+    fn revoke(&self, id: Uuid, account_id: Uuid) -> BoxFuture<'_, Result<bool, SessionStoreError>>;
+
+    /// Deletes all sessions of `account_id` but `except` and returns their number.
+    fn revoke_all(
+        &self,
+        account_id: Uuid,
+        except: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<u64, SessionStoreError>>;
+
+    /// Deletes the sessions expired before `before` and returns their number.
+    fn purge_expired(&self, before: NaiveDateTime)
+        -> BoxFuture<'_, Result<u64, SessionStoreError>>;
+}
+
+/// Creates the configured store.
+///
+/// `expiry_mins` is the session expiry of the memory store, Postgres uses the default of the
+/// `session` table.
+pub fn new_session_store(
+    settings: &SessionStoreSettings,
+    db_pool: &Pool<Postgres>,
+    expiry_mins: u8,
+) -> Arc<dyn SessionStore> {
+    match settings {
+        SessionStoreSettings::Postgres => Arc::new(PostgresSessionStore::new(db_pool.clone())),
+        SessionStoreSettings::Memory => {
+            log!(
+                Level::Warn,
+                "Memory session store configured, sessions are lost on restart"
+            );
+            Arc::new(MemorySessionStore::new(expiry_mins))
+        }
+    }
+}
+
+/// Keeps the sessions in the `session` table.
+pub struct PostgresSessionStore {
+    db_pool: Pool<Postgres>,
+}
+
+impl PostgresSessionStore {
+    pub fn new(db_pool: Pool<Postgres>) -> Self {
+        Self { db_pool }
+    }
+}
+
+impl SessionStore for PostgresSessionStore {
+    fn create(&self, session: NewSession) -> BoxFuture<'_, Result<Session, SessionStoreError>> {
+        Box::pin(async move {
+            query_as!(
+                Session,
+                "\
+                    INSERT INTO session (account_id, user_agent, impersonator_id, csrf_token_hash) \
+                    VALUES ($1, $2, $3, $4) \
+                    RETURNING id, account_id, created_at, last_used_at, expires_at, user_agent, \
+                        impersonator_id, csrf_token_hash\
+                ",
+                session.account_id,
+                session.user_agent,
+                session.impersonator_id,
+                session.csrf_token_hash
+            )
+            .fetch_one(&self.db_pool)
+            .await
+            .map_err(|err| SessionStoreError(format!("Error inserting session: {}", err)))
+        })
+    }
+
+    fn find(&self, id: Uuid) -> BoxFuture<'_, Result<Option<Session>, SessionStoreError>> {
+        Box::pin(async move {
+            query_as!(
+                Session,
+                "\
+                    SELECT id, account_id, created_at, last_used_at, expires_at, user_agent, \
+                        impersonator_id, csrf_token_hash \
+                    FROM session \
+                    WHERE id = $1\
+                ",
+                id
+            )
+            .fetch_optional(&self.db_pool)
+            .await
+            .map_err(|err| SessionStoreError(format!("Error selecting session: {}", err)))
+        })
+    }
+
+    fn list(&self, account_id: Uuid) -> BoxFuture<'_, Result<Vec<Session>, SessionStoreError>> {
+        Box::pin(async move {
+            query_as!(
+                Session,
+                "\
+                    SELECT id, account_id, created_at, last_used_at, expires_at, user_agent, \
+                        impersonator_id, csrf_token_hash \
+                    FROM session \
+                    WHERE account_id = $1 AND expires_at > LOCALTIMESTAMP \
+                    ORDER BY last_used_at DESC\
+                ",
+                account_id
+            )
+            .fetch_all(&self.db_pool)
+            .await
+            .map_err(|err| SessionStoreError(format!("Error selecting sessions: {}", err)))
+        })
+    }
+
+    fn touch(&self, id: Uuid) -> BoxFuture<'_, Result<Option<NaiveDateTime>, SessionStoreError>> {
+        Box::pin(async move {
+            query!(
+                "\
+                    UPDATE session SET expires_at = DEFAULT, last_used_at = DEFAULT \
+                    WHERE id = $1 \
+                    RETURNING expires_at\
+                ",
+                id
+            )
+            .fetch_optional(&self.db_pool)
+            .await
+            .map(|row| row.map(|row| row.expires_at))
+            .map_err(|err| SessionStoreError(format!("Error updating session: {}", err)))
+        })
+    }
+
+    fn revoke(&self, id: Uuid, account_id: Uuid) -> BoxFuture<'_, Result<bool, SessionStoreError>> {
+        Box::pin(async move {
+            query!(
+                "\
+                    DELETE FROM session \
+                    WHERE id = $1 AND account_id = $2\
+                ",
+                id,
+                account_id
+            )
+            .execute(&self.db_pool)
+            .await
+            .map(|result| result.rows_affected() > 0)
+            .map_err(|err| SessionStoreError(format!("Error deleting session: {}", err)))
+        })
+    }
+
+    fn revoke_all(
+        &self,
+        account_id: Uuid,
+        except: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<u64, SessionStoreError>> {
+        Box::pin(async move {
+            query!(
+                "\
+                    DELETE FROM session \
+                    WHERE account_id = $1 AND id IS DISTINCT FROM $2\
+                ",
+                account_id,
+                except
+            )
+            .execute(&self.db_pool)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(|err| SessionStoreError(format!("Error deleting sessions: {}", err)))
+        })
+    }
+
+    fn purge_expired(
+        &self,
+        before: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<u64, SessionStoreError>> {
+        Box::pin(async move {
+            query!(
+                "\
+                    DELETE FROM session \
+                    WHERE expires_at < $1\
+                ",
+                before
+            )
+            .execute(&self.db_pool)
+            .await
+            .map(|result| result.rows_affected())
+            .map_err(|err| SessionStoreError(format!("Error deleting sessions: {}", err)))
+        })
+    }
+}
+
+/// Keeps the sessions in memory, meant for tests and development with a single server.
+///
+/// The sessions are lost on restart and aren't shared between servers.
+pub struct MemorySessionStore {
+    expiry: TimeDelta,
+    sessions: Mutex<HashMap<Uuid, Session>>,
+}
+
+impl MemorySessionStore {
+    pub fn new(expiry_mins: u8) -> Self {
+        Self {
+            expiry: TimeDelta::minutes(expiry_mins as i64),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, Session>> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    fn create(&self, session: NewSession) -> BoxFuture<'_, Result<Session, SessionStoreError>> {
+        Box::pin(async move {
+            let mut bytes = [0u8; 16];
+            getrandom::fill(&mut bytes).map_err(|err| {
+                SessionStoreError(format!("Error generating session id: {}", err))
+            })?;
+            let now = Utc::now().naive_utc();
+            let session = Session {
+                id: Builder::from_random_bytes(bytes).into_uuid(),
+                account_id: session.account_id,
+                created_at: now,
+                last_used_at: now,
+                expires_at: now + self.expiry,
+                user_agent: session.user_agent,
+                impersonator_id: session.impersonator_id,
+                csrf_token_hash: session.csrf_token_hash,
+            };
+            self.sessions().insert(session.id, session.clone());
+
+            Ok(session)
+        })
+    }
+
+    fn find(&self, id: Uuid) -> BoxFuture<'_, Result<Option<Session>, SessionStoreError>> {
+        Box::pin(async move { Ok(self.sessions().get(&id).cloned()) })
+    }
+
+    fn list(&self, account_id: Uuid) -> BoxFuture<'_, Result<Vec<Session>, SessionStoreError>> {
+        Box::pin(async move {
+            let now = Utc::now().naive_utc();
+            let mut sessions = self
+                .sessions()
+                .values()
+                .filter(|session| session.account_id == account_id && session.expires_at > now)
+                .cloned()
+                .collect::<Vec<_>>();
+            sessions.sort_by_key(|session| Reverse(session.last_used_at));
+
+            Ok(sessions)
+        })
+    }
+
+    fn touch(&self, id: Uuid) -> BoxFuture<'_, Result<Option<NaiveDateTime>, SessionStoreError>> {
+        Box::pin(async move {
+            Ok(self.sessions().get_mut(&id).map(|session| {
+                session.last_used_at = Utc::now().naive_utc();
+                session.expires_at = session.last_used_at + self.expiry;
+                session.expires_at
+            }))
+        })
+    }
+
+    fn revoke(&self, id: Uuid, account_id: Uuid) -> BoxFuture<'_, Result<bool, SessionStoreError>> {
+        Box::pin(async move {
+            let mut sessions = self.sessions();
+            match sessions.get(&id) {
+                Some(session) if session.account_id == account_id => {
+                    sessions.remove(&id);
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        })
+    }
+
+    fn revoke_all(
+        &self,
+        account_id: Uuid,
+        except: Option<Uuid>,
+    ) -> BoxFuture<'_, Result<u64, SessionStoreError>> {
+        Box::pin(async move {
+            let mut sessions = self.sessions();
+            let count = sessions.len();
+            sessions.retain(|id, session| session.account_id != account_id || Some(*id) == except);
+
+            Ok((count - sessions.len()) as u64)
+        })
+    }
+
+    fn purge_expired(
+        &self,
+        before: NaiveDateTime,
+    ) -> BoxFuture<'_, Result<u64, SessionStoreError>> {
+        Box::pin(async move {
+            let mut sessions = self.sessions();
+            let count = sessions.len();
+            sessions.retain(|_, session| session.expires_at >= before);
+
+            Ok((count - sessions.len()) as u64)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_session(account_id: Uuid) -> NewSession {
+        NewSession {
+            account_id,
+            user_agent: Some("test".to_string()),
+            impersonator_id: None,
+            csrf_token_hash: "hash".to_string(),
+        }
+    }
+
+    fn account_id(byte: u8) -> Uuid {
+        Builder::from_random_bytes([byte; 16]).into_uuid()
+    }
+
+    #[tokio::test]
+    async fn created_sessions_are_found() {
+        let store = MemorySessionStore::new(10);
+        let session = store.create(new_session(account_id(1))).await.unwrap();
+
+        let found = store.find(session.id).await.unwrap().unwrap();
+        assert_eq!(found.account_id, account_id(1));
+        assert_eq!(found.user_agent.as_deref(), Some("test"));
+        assert_eq!(found.csrf_token_hash, "hash");
+        assert!(found.expires_at > Utc::now().naive_utc());
+        assert!(store.find(account_id(2)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn touch_extends_the_session() {
+        let store = MemorySessionStore::new(10);
+        let session = store.create(new_session(account_id(1))).await.unwrap();
+        store.sessions().get_mut(&session.id).unwrap().expires_at = session.created_at;
+
+        let expires_at = store.touch(session.id).await.unwrap().unwrap();
+        assert!(expires_at > session.created_at);
+        assert_eq!(
+            store.find(session.id).await.unwrap().unwrap().expires_at,
+            expires_at
+        );
+        assert!(store.touch(account_id(2)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn revoke_needs_the_own_account() {
+        let store = MemorySessionStore::new(10);
+        let session = store.create(new_session(account_id(1))).await.unwrap();
+
+        assert!(!store.revoke(session.id, account_id(2)).await.unwrap());
+        assert!(store.find(session.id).await.unwrap().is_some());
+        assert!(store.revoke(session.id, account_id(1)).await.unwrap());
+        assert!(store.find(session.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn revoke_all_keeps_the_exception_and_other_accounts() {
+        let store = MemorySessionStore::new(10);
+        let current = store.create(new_session(account_id(1))).await.unwrap();
+        store.create(new_session(account_id(1))).await.unwrap();
+        let other = store.create(new_session(account_id(2))).await.unwrap();
+
+        assert_eq!(
+            store
+                .revoke_all(account_id(1), Some(current.id))
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(store.find(current.id).await.unwrap().is_some());
+        assert!(store.find(other.id).await.unwrap().is_some());
+        assert_eq!(store.revoke_all(account_id(1), None).await.unwrap(), 1);
+        assert!(store.find(current.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn list_returns_unexpired_sessions_of_the_account() {
+        let store = MemorySessionStore::new(10);
+        let expired = store.create(new_session(account_id(1))).await.unwrap();
+        let older = store.create(new_session(account_id(1))).await.unwrap();
+        let newer = store.create(new_session(account_id(1))).await.unwrap();
+        store.create(new_session(account_id(2))).await.unwrap();
+        {
+            let mut sessions = store.sessions();
+            sessions.get_mut(&expired.id).unwrap().expires_at = expired.created_at;
+            sessions.get_mut(&older.id).unwrap().last_used_at -= TimeDelta::minutes(1);
+        }
+
+        let ids = store
+            .list(account_id(1))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|session| session.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![newer.id, older.id]);
+    }
+
+    #[tokio::test]
+    async fn purge_expired_deletes_sessions_expired_before() {
+        let store = MemorySessionStore::new(10);
+        let expired = store.create(new_session(account_id(1))).await.unwrap();
+        let active = store.create(new_session(account_id(1))).await.unwrap();
+        store.sessions().get_mut(&expired.id).unwrap().expires_at -= TimeDelta::minutes(30);
+
+        let purged = store.purge_expired(Utc::now().naive_utc()).await.unwrap();
+        assert_eq!(purged, 1);
+        assert!(store.find(expired.id).await.unwrap().is_none());
+        assert!(store.find(active.id).await.unwrap().is_some());
+    }
+}
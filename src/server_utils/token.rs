@@ -0,0 +1,40 @@
+use sha2::{Digest, Sha256};
+
+/// Marks personal API tokens, so they can be told apart from session JWTs.
+pub const API_TOKEN_PREFIX: &str = "lsf_";
+
+/// Generates a new random one-time token, e.g., for a password reset link.
+///
+/// # Returns
+/// - `Ok((String, String))`: The token to hand out and its hash to store.
+pub fn generate_token() -> Result<(String, String), getrandom::Error> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)?;
+    let token = to_hex(&bytes);
+    let token_hash = hash_token(&token);
+
+    Ok((token, token_hash))
+}
+
+/// Generates a new personal API token.
+///
+/// # Returns
+/// - `Ok((String, String))`: The token with its prefix to hand out once and its hash to store.
+pub fn generate_api_token() -> Result<(String, String), getrandom::Error> {
+    let (token, _) = generate_token()?;
+    let api_token = format!("{}{}", API_TOKEN_PREFIX, token);
+    let api_token_hash = hash_token(&api_token);
+
+    Ok((api_token, api_token_hash))
+}
+
+/// Hashes a one-time token, so a leaked table doesn't leak usable tokens.
+///
+/// As tokens are long and random, a plain SHA-256 is sufficient.
+pub fn hash_token(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,100 @@
+use crate::server_utils::token::hash_token;
+use chrono::Utc;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// The number of recovery codes handed out when enabling 2FA.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+// the RFC 6238 defaults, which every authenticator app supports
+const DIGITS: usize = 6;
+const STEP_SECS: i64 = 30;
+/// Codes of the previous and the next time step are accepted, too, to allow for clock drift.
+const SKEW_STEPS: i64 = 1;
+/// Lowercase letters and digits without the easily confused ones.
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_HALF_LENGTH: usize = 5;
+
+/// Generates a new random secret of 160 bits as recommended by RFC 4226.
+///
+/// # Returns
+/// - `Ok(String)`: The base32 encoded secret.
+pub fn generate_secret() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 20];
+    getrandom::fill(&mut bytes)?;
+
+    Ok(Secret::Raw(bytes.to_vec()).to_encoded().to_string())
+}
+
+/// Creates the TOTP for the base32 encoded `secret` of the account named `account_name`.
+pub fn new_totp(secret: &str, issuer: &str, account_name: &str) -> Result<TOTP, String> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|err| err.to_string())?;
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        SKEW_STEPS as u8,
+        STEP_SECS as u64,
+        secret,
+        Some(issuer.to_string()),
+        account_name.to_string(),
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Checks a code against the current time steps.
+///
+/// Steps up to `last_used_step` are skipped, so a code can't be used twice.
+///
+/// # Returns
+/// - `Some(i64)`: The time step the code is valid for, to be stored as the last used one.
+/// - `None`: If the code isn't valid.
+pub fn find_matching_step(totp: &TOTP, code: &str, last_used_step: Option<i64>) -> Option<i64> {
+    let current_step = Utc::now().timestamp() / STEP_SECS;
+    (current_step - SKEW_STEPS..=current_step + SKEW_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last_used_step| *step > last_used_step))
+        .find(|step| totp.generate((step * STEP_SECS) as u64) == code)
+}
+
+/// Removes whitespace and dashes users might type, so codes can be compared.
+pub fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|char| !char.is_whitespace() && *char != '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Generates new recovery codes, each usable once instead of a TOTP code.
+///
+/// # Returns
+/// - `Ok(Vec<(String, String)>)`: The codes to show to the user and their hashes to store.
+pub fn generate_recovery_codes() -> Result<Vec<(String, String)>, getrandom::Error> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = format!(
+                "{}-{}",
+                random_chars(RECOVERY_CODE_HALF_LENGTH)?,
+                random_chars(RECOVERY_CODE_HALF_LENGTH)?
+            );
+            let code_hash = hash_token(&normalize_code(&code));
+            Ok((code, code_hash))
+        })
+        .collect()
+}
+
+fn random_chars(length: usize) -> Result<String, getrandom::Error> {
+    // rejecting bytes above a multiple of the alphabet length avoids a modulo bias
+    let limit =
+        (u8::MAX as usize + 1) / RECOVERY_CODE_ALPHABET.len() * RECOVERY_CODE_ALPHABET.len();
+    let mut chars = String::with_capacity(length);
+    while chars.len() < length {
+        let mut byte = [0u8; 1];
+        getrandom::fill(&mut byte)?;
+        if (byte[0] as usize) < limit {
+            chars.push(
+                RECOVERY_CODE_ALPHABET[byte[0] as usize % RECOVERY_CODE_ALPHABET.len()] as char,
+            );
+        }
+    }
+
+    Ok(chars)
+}
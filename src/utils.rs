@@ -1,6 +1,15 @@
 use leptos::context::use_context;
-use leptos::prelude::ReadSignal;
+use leptos::html::input;
+use leptos::prelude::{GetUntracked, IntoView, ReadSignal, Set, WriteSignal};
 use crate::i18n::{use_i18n, Locale};
+use chrono::{DateTime, Local};
+use wasm_bindgen::JsCast;
+
+/// The cookie the server sets at login with the CSRF token of the session.
+pub const CSRF_COOKIE: &str = "lsf_csrf";
+/// The header server fns expect the CSRF token in, form posts can use the `CSRF_FIELD` instead.
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+pub const CSRF_FIELD: &str = "csrf_token";
 
 pub fn get_lang_from_browser() -> Option<String> {
     let window = web_sys::window().expect("no global `window` exists");
@@ -11,10 +20,9 @@ pub fn get_lang_from_browser() -> Option<String> {
         .get_item("lang")
         .expect("failed to get lang from storage");
 
-    if local_storage_lang.is_none() && navigator_lang.is_some() {
-        Some(navigator_lang.unwrap()[0..2].to_string())
-    } else {
-        local_storage_lang
+    match (local_storage_lang, navigator_lang) {
+        (None, Some(navigator_lang)) => Some(navigator_lang[0..2].to_string()),
+        (local_storage_lang, _) => local_storage_lang,
     }
 }
 
@@ -36,20 +44,96 @@ fn set_to_session_storage(key: &str, value: &str) {
     session_storage.set_item(key, value).unwrap();
 }
 
+fn remove_from_session_storage(key: &str) {
+    let window = web_sys::window().expect("no global `window` exists");
+    let session_storage = window
+        .session_storage()
+        .expect("no global session storage exists")
+        .unwrap();
+    session_storage.remove_item(key).unwrap();
+}
+
 pub fn set_login_data_to_session_storage(token: &str, expires: i64) {
     set_to_session_storage("token", token);
     set_to_session_storage("expires", expires.to_string().as_str());
 }
 
-pub fn get_login_data_from_session_storage() -> (String, i64) {
+pub fn remove_login_data_from_session_storage() {
+    remove_from_session_storage("token");
+    remove_from_session_storage("expires");
+}
+
+/// Loads `url` from scratch, so the user is restored from the session stored last.
+pub fn reload_at(url: &str) {
+    let window = web_sys::window().expect("no global `window` exists");
+    window
+        .location()
+        .set_href(url)
+        .expect("failed to set location");
+}
+
+/// Returns the token and expiry timestamp stored at login, if there are any.
+///
+/// If the session token is an `HttpOnly` cookie, the token is empty and only the expiry is
+/// known.
+pub fn get_login_data_from_session_storage() -> Option<(String, i64)> {
     let window = web_sys::window().expect("no global `window` exists");
     let session_storage = window
         .session_storage()
         .expect("no global session storage exists")
         .expect("no session storage exists");
-    let token = session_storage.get_item("token").expect("no token in session storage");
-    let expires = session_storage.get_item("expires").expect("no expires in session storage");
-    (token.unwrap(), expires.unwrap().parse::<i64>().unwrap())
+    let token = session_storage.get_item("token").expect("failed to get token from storage");
+    let expires = session_storage
+        .get_item("expires")
+        .expect("failed to get expires from storage");
+    match (token, expires.and_then(|expires| expires.parse::<i64>().ok())) {
+        (Some(token), Some(expires)) if !token.is_empty() || expires > 0 => {
+            Some((token, expires))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the CSRF token of the session, if there is one.
+///
+/// Unlike the session cookie, its cookie is readable for scripts, so it can be sent as header.
+pub fn get_csrf_token_from_cookie() -> Option<String> {
+    let document = web_sys::window()
+        .expect("no global `window` exists")
+        .document()
+        .expect("no document exists");
+    let cookies = document
+        .dyn_into::<web_sys::HtmlDocument>()
+        .expect("document is no HTML document")
+        .cookie()
+        .unwrap_or_default();
+    cookies
+        .split(';')
+        .find_map(|cookie| cookie.trim().strip_prefix(CSRF_COOKIE)?.strip_prefix('='))
+        .map(|token| token.to_string())
+}
+
+/// Returns the CSRF token of the session, read from the request while rendering on the server.
+fn get_csrf_token() -> Option<String> {
+    #[cfg(feature = "ssr")]
+    {
+        use_context::<actix_web::HttpRequest>()
+            .and_then(|req| req.cookie(CSRF_COOKIE))
+            .map(|cookie| cookie.value().to_string())
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        get_csrf_token_from_cookie()
+    }
+}
+
+/// The hidden `CSRF_FIELD` for forms of a session, which are posted without the `CSRF_HEADER`
+/// before the hydration or without JS.
+pub fn csrf_field() -> impl IntoView {
+    input()
+        .r#type("hidden")
+        .name(CSRF_FIELD)
+        .value(get_csrf_token().unwrap_or_default())
 }
 
 pub fn set_lang_to_i18n(lang: &str) {
@@ -64,3 +148,25 @@ pub fn set_lang_to_i18n(lang: &str) {
 pub fn get_lang() -> ReadSignal<String> {
     use_context::<ReadSignal<String>>().expect("lang missing from context")
 }
+
+/// Makes the preferred language of a logged-in user the one used by the client.
+pub fn set_user_lang(user_lang: &str, lang: ReadSignal<String>, lang_setter: WriteSignal<String>) {
+    // shouldn't rerun on changes of lang
+    if user_lang != lang.get_untracked() {
+        lang_setter.set(user_lang.to_string());
+        set_lang_to_locale_storage(user_lang);
+        set_lang_to_i18n(user_lang);
+    }
+}
+
+/// Formats a UTC timestamp in seconds as a date and time in the user's time zone.
+pub fn format_timestamp(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date_time| {
+            date_time
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_default()
+}